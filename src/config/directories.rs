@@ -4,6 +4,11 @@ use std::path::PathBuf;
 pub const CSS_FILE_REL: &str = ".fingerboard/data/theming/style.css";
 pub const LAYOUT_PATH_REL: &str = ".fingerboard/data/keyboards";
 pub const ICON_DIR_REL: &str = ".fingerboard/data/icons/";
+pub const ACCENT_DIR_REL: &str = ".fingerboard/data/accents";
+pub const PUNCTUATION_DIR_REL: &str = ".fingerboard/data/punctuation";
+pub const PINYIN_DICTIONARY_FILE_REL: &str = ".fingerboard/data/pinyin/dictionary.txt";
+pub const USER_DICTIONARY_DIR_REL: &str = "fingerboard/user_dictionary";
+pub const SNIPPETS_FILE_REL: &str = "fingerboard/snippets.txt";
 
 /// Get the absolute path from a relative path
 /// The absolute path assumes the current directory is the users HOME directory
@@ -20,3 +25,21 @@ pub fn get_absolute_path(path_in_home_dir: &str) -> Option<PathBuf> {
         None
     }
 }
+
+/// Get the absolute path from a path relative to the users data directory
+/// The data directory is $XDG_DATA_HOME or, if that variable is not set, '~/.local/share'
+pub fn get_absolute_data_path(path_in_data_dir: &str) -> Option<PathBuf> {
+    let xdg_data_home = envmnt::get_or("XDG_DATA_HOME", "");
+    // Relative paths in XDG_DATA_HOME are invalid and have to be ignored
+    let data_dir = if !xdg_data_home.is_empty() && PathBuf::from(&xdg_data_home).is_absolute() {
+        Some(PathBuf::from(xdg_data_home))
+    } else {
+        get_absolute_path(".local/share")
+    };
+    data_dir.map(|mut new_path| {
+        // Append the relative path
+        new_path.push(path_in_data_dir);
+        info!("Data path is {:?}", new_path);
+        new_path
+    })
+}
@@ -1 +1,12 @@
-pub const LONG_PRESS_DELAY_FACTOR: f64 = 0.7; // Factor by which to modify the default timeout. Allowed values: [0.5,2]
+pub const LONG_PRESS_DELAY: u64 = 350; // Milliseconds a key needs to be held for a long press, unless the layout or the key defines a different delay
+pub const KEY_REPEAT_INTERVAL: u64 = 60; // Milliseconds between two repetitions of a key that is held down (e.g. the arrow keys of the terminal layout)
+pub const MULTI_TAP_TIMEOUT: u64 = 800; // Milliseconds after the last tap of a multi-tap key until its character is committed
+pub const MAX_PINYIN_CANDIDATES: usize = 50; // Maximum number of words that are shown in the suggestion bar for the typed pinyin
+pub const DOUBLE_TAP_DELAY: u64 = 300; // Maximum milliseconds between the end of a tap and the beginning of the next tap for them to be a double tap
+pub const DOUBLE_TAP_MAX_DISTANCE: f64 = 30.0; // Maximum distance (in pixels) between two taps for them to be a double tap
+pub const LEARN_WORD_THRESHOLD: u32 = 2; // Number of times a word needs to be committed before it is added to the user dictionary
+pub const SAVE_COUNTS_INTERVAL: u32 = 10; // Number of commits of learned words after which their changed counts are saved to the user dictionary
+pub const REDACT_TYPED_TEXT_ENV: &str = "FINGERBOARD_REDACT_TEXT"; // If this environment variable is set to true, typed text is redacted from the logs everywhere
+pub const SWIPE_ERASE_WORD_DISTANCE: f64 = 0.1; // Distance (relative to the width of the keyboard) the finger needs to move left on the BackSpace key to erase one more word
+pub const CURSOR_MOVEMENT_DISTANCE: f64 = 0.04; // Distance (relative to the width of the keyboard) the finger needs to move sideways on the space key to move the cursor by one char
+pub const FLICK_MAX_DISTANCE: f64 = 0.15; // Maximum distance (relative to the width of the keyboard) the finger can move away from the start of a flick. Swipes that go further are gestures
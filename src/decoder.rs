@@ -1,27 +1,69 @@
 // Imports from other crates
+use std::collections::HashMap;
 use std::sync::mpsc;
+use unicode_segmentation::UnicodeSegmentation;
 
 // Imports from other modules
-use crate::keyboard::UIConnector;
-use crate::submitter::Submission;
+use crate::keyboard::{Modifier, UIConnector};
+use crate::privacy::{self, redact};
+use crate::submitter::{Submission, KEY_BACKSPACE, KEY_DELETE, KEY_LEFT, KEY_RIGHT};
 #[cfg(feature = "suggestions")]
 use crate::user_interface::Msg;
 use input_decoder::InputDecoder;
 
+// Modules
+mod punctuation;
+mod snippets;
+mod user_dictionary;
+use punctuation::PunctuationRules;
+use snippets::{Snippets, CURSOR_MARKER};
+
+// Re-exports
+pub use user_dictionary::UserDictionary;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Parts of the text around the cursor that can be erased
+pub enum Erasure {
+    PreviousGrapheme,
+    PreviousWord,
+    NextGrapheme,
+    Line,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Directions the cursor can be moved in
+pub enum CursorMovement {
+    Left,
+    Right,
+}
+
 /// The Decoder attempts to correct errors and guess the submission the user had in mind when clicking the key.
 pub struct Decoder {
     ui_connection: UIConnector,
-    receiver: mpsc::Receiver<(String, String)>, // Receives the surrounding text
+    receiver: mpsc::Receiver<Option<(String, String)>>, // Receives the surrounding text or 'None' if a different text field was focused
     text_left_of_cursor: String,
     text_right_of_cursor: String,
+    surrounding_text_known: bool, // Not all applications support sending the surrounding text
+    surrounding_text_changed: bool, // True if the surrounding text changed since it was last checked
     input_decoder: InputDecoder,
     previous_words: Vec<String>,
     drawn_path: Vec<(f64, f64)>,
+    key_centers: HashMap<char, (f64, f64)>, // Needed to compare the drawn path to the ideal paths of learned words
+    user_dictionary: UserDictionary,
+    punctuation_rules: PunctuationRules,
+    punctuation_rules_enabled: bool, // The rules are not applied where punctuation has a different meaning (e.g. in URLs)
+    auto_space: bool, // True if the last submission was a space that was inserted automatically
+    snippets: Snippets,
+    snippet_suggestion: Option<(String, String)>, // Label and trigger of the snippet that is currently suggested
+    completion: Option<(Vec<String>, String)>, // Suggested completions and the beginning of the word they complete
 }
 
 impl Decoder {
     /// Create a new Decoder
-    pub fn new(ui_connection: UIConnector, receiver: mpsc::Receiver<(String, String)>) -> Decoder {
+    pub fn new(
+        ui_connection: UIConnector,
+        receiver: mpsc::Receiver<Option<(String, String)>>,
+    ) -> Decoder {
         let text_left_of_cursor = "".to_string();
         let text_right_of_cursor = "".to_string();
         let input_decoder = InputDecoder::new(
@@ -31,42 +73,146 @@ impl Decoder {
         );
         let previous_words = Vec::new();
         let drawn_path = Vec::new();
+        let key_centers = HashMap::new();
+        let user_dictionary = UserDictionary::load(&crate::get_locale_language());
+        let punctuation_rules = PunctuationRules::load(&crate::get_locale_language());
         Decoder {
             ui_connection,
             receiver,
             text_left_of_cursor,
             text_right_of_cursor,
+            surrounding_text_known: false,
+            surrounding_text_changed: false,
             input_decoder,
             previous_words,
             drawn_path,
+            key_centers,
+            user_dictionary,
+            punctuation_rules,
+            punctuation_rules_enabled: true,
+            auto_space: false,
+            snippets: Snippets::load(),
+            snippet_suggestion: None,
+            completion: None,
+        }
+    }
+
+    /// Set the centers of the keys of the active view
+    /// They are used to calculate the ideal paths of the learned words
+    pub fn set_key_centers(&mut self, key_centers: HashMap<char, (f64, f64)>) {
+        self.key_centers = key_centers;
+    }
+
+    /// Remove the word from the user dictionary so it no longer gets suggested
+    pub fn forget_word(&mut self, word: &str) {
+        if self.user_dictionary.remove(word) {
+            info!("Removed '{}' from the user dictionary", redact(word));
+        } else {
+            warn!(
+                "Unable to remove '{}' because it is not part of the user dictionary",
+                redact(word)
+            );
         }
+        // Do not keep suggesting the removed word
+        #[cfg(feature = "suggestions")]
+        self.update_suggestions();
+    }
+
+    /// Forget that the last submission was a space that was inserted automatically, e.g. because a different text field was focused
+    pub fn reset_auto_space(&mut self) {
+        self.auto_space = false;
+    }
+
+    /// Enable or disable the punctuation rules
+    /// They are disabled in text inputs where punctuation has a different meaning (e.g. URLs, email addresses and terminals)
+    pub fn set_punctuation_rules_enabled(&mut self, enabled: bool) {
+        self.punctuation_rules_enabled = enabled;
     }
 
     /// Decodes the text that would have been sent while considering the surrounding text and previous submissions.
     /// It returns a vector of the submissions it is assumed the user had intended and updates the suggested next words
     pub fn decode_text(&mut self, text_to_decode: String) -> Vec<Submission> {
+        self.decode(text_to_decode, false)
+    }
+
+    /// Decodes the space that is inserted automatically after a word (e.g. after gesture typing)
+    /// Punctuation that directly follows it can remove it again
+    pub fn decode_automatic_space(&mut self) -> Vec<Submission> {
+        let submissions = self.decode(" ".to_string(), true);
+        self.auto_space = true;
+        submissions
+    }
+
+    /// Decodes the text. An automatically inserted space is never replaced with ". "
+    fn decode(&mut self, text_to_decode: String, automatic_space: bool) -> Vec<Submission> {
+        self.snippet_suggestion = None;
+        self.completion = None;
         self.update_surrounding_text();
+        // The space is only removed again if it is still in front of the cursor
+        let auto_space = std::mem::take(&mut self.auto_space)
+            && (!self.surrounding_text_known || self.text_left_of_cursor.ends_with(' '));
         info!("Received the surrounding text:");
-        info!("Left of the cursor: {}", self.text_left_of_cursor);
-        info!("Right of the cursor: {}", self.text_right_of_cursor);
+        info!("Left of the cursor: {}", redact(&self.text_left_of_cursor));
+        info!(
+            "Right of the cursor: {}",
+            redact(&self.text_right_of_cursor)
+        );
 
         let mut new_submissions = Vec::new();
+        // Text typed into sensitive fields is submitted as it is. It is neither corrected nor learned from
+        if privacy::is_sensitive_field() {
+            new_submissions.push(Submission::Text(text_to_decode));
+            return new_submissions;
+        }
+        // Correct the spacing around punctuation
+        if self.punctuation_rules_enabled {
+            if let Some(submissions) = self.decode_punctuation(&text_to_decode, auto_space) {
+                return submissions;
+            }
+        }
         // If the current and the previous text submission are a SPACE, it is assumed a sentence was terminated and the previous space gets replaced with a dot
+        let double_space = self
+            .punctuation_rules
+            .get_double_space()
+            .filter(|_| self.punctuation_rules_enabled);
         if text_to_decode.ends_with(' ') {
-            if text_to_decode == " " && self.text_left_of_cursor.ends_with(' ') {
-                info!("End of sentence suspected because space was entered twice in a row. Will be replaced with '. '");
-                new_submissions.push(Submission::Erase(1));
-                new_submissions.push(Submission::Text(". ".to_string()));
+            if let Some(punctuation_mark) = double_space.filter(|_| {
+                text_to_decode == " " && !automatic_space && self.text_left_of_cursor.ends_with(' ')
+            }) {
+                info!(
+                    "End of sentence suspected because space was entered twice in a row. Will be replaced with '{} '",
+                    punctuation_mark
+                );
+                new_submissions.push(Submission::Erase(" ".to_string()));
+                new_submissions.push(Submission::Text(format!("{punctuation_mark} ")));
             } else {
                 // Notify the input decoder about the changed previous words
                 let no_new_words = self.update_last_words();
-                for word in self
+                // A snippet is expanded when its trigger is followed by a typed space
+                if let Some(trigger) = self
+                    .previous_words
+                    .last()
+                    .filter(|word| {
+                        text_to_decode == " "
+                            && !automatic_space
+                            && no_new_words > 0
+                            && self.text_left_of_cursor.ends_with(word.as_str())
+                            && self.snippets.get(word).is_some()
+                    })
+                    .cloned()
+                {
+                    return self.expand_snippet(&trigger);
+                }
+                let new_words: Vec<String> = self
                     .previous_words
                     .iter()
                     .skip(self.previous_words.len() - no_new_words)
-                {
-                    info!("Entered '{}' into decoder", word);
-                    self.input_decoder.entered_word(word);
+                    .cloned()
+                    .collect();
+                for word in new_words {
+                    info!("Entered '{}' into decoder", redact(&word));
+                    self.input_decoder.entered_word(&word);
+                    self.learn_word(&word);
                 }
 
                 // Notify the UI about new suggestions
@@ -75,23 +221,280 @@ impl Decoder {
                 new_submissions.push(Submission::Text(text_to_decode));
             }
         } else {
+            // Suggest learned words that complete the word that is currently typed
+            #[cfg(feature = "suggestions")]
+            self.suggest_completions(&text_to_decode);
             new_submissions.push(Submission::Text(text_to_decode));
         }
 
         new_submissions
     }
 
+    /// Decodes a suggestion that was chosen from the suggestion bar
+    /// Returns 'None' if it is not a snippet or its trigger is no longer in front of the cursor. Then it is entered as it is
+    pub fn decode_suggestion(&mut self, suggestion: &str) -> Option<Vec<Submission>> {
+        let (label, trigger) = self.snippet_suggestion.take()?;
+        if label != suggestion {
+            return None;
+        }
+        self.update_surrounding_text();
+        if self.surrounding_text_known && !self.text_left_of_cursor.ends_with(&trigger) {
+            return None;
+        }
+        Some(self.expand_snippet(&trigger))
+    }
+
+    /// Decodes a completion that was chosen from the suggestion bar
+    /// Returns the erasure of the beginning of the word it completes, because the completion is entered as a whole
+    /// Returns 'None' if it is not a completion or the beginning of the word is no longer in front of the cursor
+    pub fn decode_completion(&mut self, suggestion: &str) -> Option<Submission> {
+        let (completions, beginning_of_word) = self.completion.take()?;
+        if !completions
+            .iter()
+            .any(|completion| completion == suggestion)
+        {
+            return None;
+        }
+        self.update_surrounding_text();
+        if self.surrounding_text_known && !self.text_left_of_cursor.ends_with(&beginning_of_word) {
+            return None;
+        }
+        Some(Submission::Erase(beginning_of_word))
+    }
+
+    /// Replace the trigger before the cursor with the expansion of its snippet
+    /// If the expansion contains the $CURSOR marker, the cursor is moved to it
+    fn expand_snippet(&self, trigger: &str) -> Vec<Submission> {
+        let expansion = self.snippets.get(trigger).unwrap_or_default();
+        info!("Expanding the snippet of the trigger '{}'", redact(trigger));
+        let (text_before_cursor, text_after_cursor) = expansion
+            .split_once(CURSOR_MARKER)
+            .unwrap_or((expansion, ""));
+        let mut new_submissions = vec![
+            Submission::Erase(trigger.to_string()),
+            Submission::Text(format!("{text_before_cursor}{text_after_cursor}")),
+        ];
+        new_submissions.extend(
+            std::iter::repeat(Submission::Keycode(KEY_LEFT))
+                .take(text_after_cursor.graphemes(true).count()),
+        );
+        new_submissions
+    }
+
+    /// Apply the punctuation rules of the language to a punctuation mark
+    /// If it follows a space that was inserted automatically, the space is removed and a space is inserted after the punctuation mark instead
+    /// Returns 'None' if no rule applies to the text
+    fn decode_punctuation(
+        &mut self,
+        text_to_decode: &str,
+        auto_space: bool,
+    ) -> Option<Vec<Submission>> {
+        let mut chars = text_to_decode.chars();
+        let punctuation_mark = match (chars.next(), chars.next()) {
+            (Some(punctuation_mark), None) => punctuation_mark,
+            _ => return None,
+        };
+        let mut new_submissions = Vec::new();
+        let mut text = String::new();
+        if self.punctuation_rules.has_nbsp_before(punctuation_mark) {
+            // The space before the punctuation mark is replaced with a non-breaking space
+            if auto_space || self.text_left_of_cursor.ends_with(' ') {
+                new_submissions.push(Submission::Erase(" ".to_string()));
+            }
+            if auto_space || !self.text_left_of_cursor.trim_end_matches(' ').is_empty() {
+                text.push('\u{a0}');
+            }
+        } else if auto_space && self.punctuation_rules.attaches(punctuation_mark) {
+            new_submissions.push(Submission::Erase(" ".to_string()));
+        } else {
+            return None;
+        }
+        info!("Correcting the spacing around the punctuation mark");
+        text.push(punctuation_mark);
+        if auto_space && self.punctuation_rules.has_space_after(punctuation_mark) {
+            text.push(' ');
+            self.auto_space = true;
+        }
+        new_submissions.push(Submission::Text(text));
+        Some(new_submissions)
+    }
+
+    /// Get the submissions to erase a part of the text around the cursor
+    /// Some graphemes (e.g. emoji with a skin tone modifier) consist of several chars and need to be erased as a whole.
+    /// If the surrounding text is unknown, keycodes that have the same effect in most applications are submitted instead
+    pub fn decode_erase(&mut self, erasure: Erasure) -> Vec<Submission> {
+        self.auto_space = false;
+        self.update_surrounding_text();
+        if !self.surrounding_text_known {
+            info!("Surrounding text is unknown. Falling back to keycodes to erase the text");
+            return get_erase_fallback(erasure);
+        }
+
+        let (erase_from, erase_until) = get_erase_range(
+            erasure,
+            &self.text_left_of_cursor,
+            &self.text_right_of_cursor,
+        );
+
+        // The surrounding text is only updated after the erasure was committed. The text must not get erased twice until then
+        let erased_left = self.text_left_of_cursor.split_off(erase_from);
+        let erased_right: String = self.text_right_of_cursor.drain(..erase_until).collect();
+        let mut submissions = Vec::new();
+        if !erased_left.is_empty() {
+            submissions.push(Submission::Erase(erased_left));
+        }
+        if !erased_right.is_empty() {
+            submissions.push(Submission::EraseAfter(erased_right));
+        }
+        submissions
+    }
+
+    /// Get the submission to move the cursor by one grapheme cluster
+    /// If 'extend_selection' is true, the selection is extended instead (SHIFT is held while the cursor moves)
+    /// Returns 'None' if the surrounding text shows that the cursor is already at the start/end of the text
+    pub fn decode_cursor_movement(
+        &mut self,
+        movement: CursorMovement,
+        extend_selection: bool,
+    ) -> Option<Submission> {
+        self.auto_space = false;
+        self.update_surrounding_text();
+        if self.surrounding_text_known {
+            // The surrounding text is only updated after the cursor was moved. Until then the cursor is moved here
+            match movement {
+                CursorMovement::Left => {
+                    let grapheme_len = self
+                        .text_left_of_cursor
+                        .graphemes(true)
+                        .next_back()
+                        .map(str::len)?;
+                    let new_len = self.text_left_of_cursor.len() - grapheme_len;
+                    let grapheme = self.text_left_of_cursor.split_off(new_len);
+                    self.text_right_of_cursor.insert_str(0, &grapheme);
+                }
+                CursorMovement::Right => {
+                    let grapheme_len = self
+                        .text_right_of_cursor
+                        .graphemes(true)
+                        .next()
+                        .map(str::len)?;
+                    let grapheme: String =
+                        self.text_right_of_cursor.drain(..grapheme_len).collect();
+                    self.text_left_of_cursor.push_str(&grapheme);
+                }
+            }
+        }
+        let keycode = match movement {
+            CursorMovement::Left => KEY_LEFT,
+            CursorMovement::Right => KEY_RIGHT,
+        };
+        if extend_selection {
+            Some(Submission::Shortcut(vec![Modifier::Shift], keycode))
+        } else {
+            Some(Submission::Keycode(keycode))
+        }
+    }
+
+    /// Get the submission to erase the text that was committed last, e.g. the word entered with a gesture and the space after it
+    /// Returns 'None' if the surrounding text shows that the text is no longer directly before the cursor
+    pub fn decode_erase_committed(&mut self, committed_text: &str) -> Option<Submission> {
+        self.auto_space = false;
+        self.update_surrounding_text();
+        if self.surrounding_text_known {
+            if !self.text_left_of_cursor.ends_with(committed_text) {
+                info!("The committed text is no longer directly before the cursor");
+                return None;
+            }
+            // The surrounding text is only updated after the erasure was committed. The text must not get erased twice until then
+            let new_len = self.text_left_of_cursor.len() - committed_text.len();
+            self.text_left_of_cursor.truncate(new_len);
+        }
+        Some(Submission::Erase(committed_text.to_string()))
+    }
+
+    /// Get the submission to restore text that was erased before the cursor
+    /// The text is submitted as it is and not decoded
+    pub fn restore_erased_text(&mut self, erased_text: String) -> Submission {
+        // The surrounding text is only updated after the text was committed. Until then the restored text needs to be added here
+        if self.surrounding_text_known {
+            self.text_left_of_cursor.push_str(&erased_text);
+        }
+        Submission::Text(erased_text)
+    }
+
+    /// Returns the updated text left of the cursor or 'None' if the application does not send the surrounding text
+    pub fn get_current_text_left_of_cursor(&mut self) -> Option<String> {
+        self.update_surrounding_text();
+        if self.surrounding_text_known {
+            Some(self.text_left_of_cursor.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Replace the end of the text left of the cursor after an edit was undone or redone
+    pub fn replace_text_left_of_cursor(&mut self, erased_text: &str, committed_text: &str) {
+        self.auto_space = false;
+        // The surrounding text is only updated after the text was replaced. Until then it is replaced here
+        if self.text_left_of_cursor.ends_with(erased_text) {
+            let new_len = self.text_left_of_cursor.len() - erased_text.len();
+            self.text_left_of_cursor.truncate(new_len);
+            self.text_left_of_cursor.push_str(committed_text);
+        }
+    }
+
+    /// Count the commit of the word in the user dictionary
+    /// Punctuation around the word is not part of the word and gets removed
+    fn learn_word(&mut self, word: &str) {
+        // Nothing is learned from sensitive fields so that e.g. passwords never end up in the user dictionary
+        if privacy::is_sensitive_field() {
+            return;
+        }
+        let word = word.trim_matches(|c: char| !c.is_alphanumeric());
+        if word.chars().any(char::is_alphabetic) && self.user_dictionary.record_commit(word) {
+            info!("Learned the new word '{}'", redact(word));
+        }
+    }
+
+    #[cfg(feature = "suggestions")]
+    /// Notify the UI about learned words that complete the currently typed word
+    /// The text that is about to be submitted is not yet part of the surrounding text and needs to be appended
+    /// A snippet whose trigger is typed is suggested first
+    /// If nothing completes the word, the suggestions of the previous word are removed
+    fn suggest_completions(&mut self, text_to_decode: &str) {
+        let mut current_word = self
+            .text_left_of_cursor
+            .rsplit(char::is_whitespace)
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        current_word.push_str(text_to_decode);
+        let mut completions = self.user_dictionary.get_completions(&current_word, 3);
+        if let Some(label) = self.snippets.get_label(&current_word) {
+            completions.insert(0, label.clone());
+            completions.truncate(3);
+            self.snippet_suggestion = Some((label, current_word.clone()));
+        }
+        if completions.is_empty() {
+            self.completion = None;
+            self.snippet_suggestion = None;
+        } else {
+            self.completion = Some((completions.clone(), current_word));
+        }
+        self.ui_connection.emit(Msg::Suggestions(completions));
+    }
+
     // Updates the previous_words field and returns the number of new words
     fn update_last_words(&mut self) -> usize {
         let previous_words = &self.previous_words;
 
         info!(
-            "previous_words: {:?}, {:?}",
-            previous_words.get(0),
-            previous_words.get(1)
+            "previous_words: {}, {}",
+            redact(format!("{:?}", previous_words.get(0))),
+            redact(format!("{:?}", previous_words.get(1)))
         );
 
-        info!("text_left_of_cursor: {}", self.text_left_of_cursor);
+        info!("text_left_of_cursor: {}", redact(&self.text_left_of_cursor));
 
         #[allow(clippy::needless_collect)]
         let updated_words: Vec<&str> = self
@@ -107,9 +510,9 @@ impl Decoder {
             .collect();
 
         info!(
-            "updated_words: {:?}, {:?}",
-            updated_words.get(0),
-            updated_words.get(1)
+            "updated_words: {}, {}",
+            redact(format!("{:?}", updated_words.get(0))),
+            redact(format!("{:?}", updated_words.get(1)))
         );
 
         let no_changed_words =
@@ -125,17 +528,38 @@ impl Decoder {
         no_changed_words
     }
 
+    /// Returns true if the surrounding text changed since this method was called the last time
+    pub fn surrounding_text_changed(&mut self) -> bool {
+        self.update_surrounding_text();
+        std::mem::take(&mut self.surrounding_text_changed)
+    }
+
+    /// Returns the text left of the cursor as far as it is known
+    pub fn get_text_left_of_cursor(&self) -> &str {
+        &self.text_left_of_cursor
+    }
+
     /// Add the new coordinate of the drawn path
     pub fn update_gesture(&mut self, x: f64, y: f64) {
         self.drawn_path.push((x, y));
     }
 
+    /// Forget the drawn path without looking for a word (e.g. because the swipe was a flick)
+    pub fn cancel_gesture(&mut self) {
+        self.drawn_path.clear();
+    }
+
     #[cfg(feature = "suggestions")]
     // Notify the UI about new suggestions
+    // Nothing is suggested in sensitive fields
     pub fn update_suggestions(&self) {
         {
-            let predictions = self.input_decoder.get_predictions();
-            let predictions: Vec<String> = predictions.into_iter().take(3).collect();
+            let predictions = if privacy::is_sensitive_field() {
+                Vec::new()
+            } else {
+                let predictions = self.input_decoder.get_predictions();
+                predictions.into_iter().take(3).collect()
+            };
 
             self.ui_connection.emit(Msg::Suggestions(predictions));
         }
@@ -143,28 +567,52 @@ impl Decoder {
 
     /// Notify the decoder about the end of a gesture and get the most likely word
     pub fn get_gesture_result(&mut self, x: f64, y: f64) -> String {
+        // The word of the gesture does not complete the suggested beginning of a word
+        self.completion = None;
         // Add the last point to the path
         self.drawn_path.push((x, y));
 
         // Find the word with the most similar ideal path to the drawn gesture
         let predictions = self.input_decoder.find_similar_words(&self.drawn_path);
-        self.drawn_path.clear();
 
         info!("predictions and their similarity:");
         for (word, _) in predictions.iter().take(10) {
-            info!("{}", word);
+            info!("{}", redact(word));
         }
 
-        let predictions: Vec<String> = predictions
+        let mut predictions: Vec<String> = predictions
             .into_iter()
             .take(3)
             .map(|(word, _)| word)
             .collect();
 
+        // The language model does not know the learned words so they are compared with the most likely candidate of the language model
+        let best_candidate_distance = predictions
+            .first()
+            .and_then(|word| {
+                user_dictionary::get_path_distance(word, &self.drawn_path, &self.key_centers)
+            })
+            .unwrap_or(f64::MAX);
+        let learned_words = self
+            .user_dictionary
+            .find_similar_words(&self.drawn_path, &self.key_centers);
+        for (word, distance) in learned_words.into_iter().rev() {
+            if distance < best_candidate_distance && !predictions.contains(&word) {
+                info!("The learned word '{}' is more likely", redact(&word));
+                predictions.insert(0, word);
+            }
+        }
+        predictions.truncate(3);
+        self.drawn_path.clear();
+
         // Send the most likely candidates to the ui to suggest and return the most likely candidate
         let most_likely_word = predictions[0].clone();
         #[cfg(feature = "suggestions")]
-        self.ui_connection.emit(Msg::Suggestions(predictions));
+        {
+            if !privacy::is_sensitive_field() {
+                self.ui_connection.emit(Msg::Suggestions(predictions));
+            }
+        }
         most_likely_word
     }
 
@@ -193,9 +641,96 @@ impl Decoder {
                 },
             };
         }
-        if let Some((left_string, right_string)) = text_changed {
-            self.text_left_of_cursor = left_string;
-            self.text_right_of_cursor = right_string;
+        match text_changed {
+            Some(Some((left_string, right_string))) => {
+                // The text before the cursor was changed by something else than the automatic space (e.g. the cursor was moved)
+                if !left_string.ends_with(' ') {
+                    self.auto_space = false;
+                }
+                self.text_left_of_cursor = left_string;
+                self.text_right_of_cursor = right_string;
+                self.surrounding_text_known = true;
+                self.surrounding_text_changed = true;
+            }
+            // A different text field was focused and did not send its surrounding text yet
+            Some(None) => {
+                self.auto_space = false;
+                self.forget_surrounding_text();
+            }
+            None => {}
         }
     }
+
+    /// Forget the surrounding text, e.g. because a different text field was focused or a key combination changed the text
+    /// Until the application sends the new surrounding text, it is treated as unknown
+    pub fn forget_surrounding_text(&mut self) {
+        self.text_left_of_cursor.clear();
+        self.text_right_of_cursor.clear();
+        self.surrounding_text_known = false;
+    }
+}
+
+/// Get the keycodes to erase the text if the surrounding text is unknown
+/// A line is not erased, because it is unknown whether it is empty. Selecting an empty line selects nothing, so erasing the selection would erase a line break instead
+fn get_erase_fallback(erasure: Erasure) -> Vec<Submission> {
+    match erasure {
+        Erasure::PreviousGrapheme => vec![Submission::Keycode(KEY_BACKSPACE)],
+        Erasure::PreviousWord => {
+            vec![Submission::Shortcut(vec![Modifier::Control], KEY_BACKSPACE)]
+        }
+        Erasure::NextGrapheme => vec![Submission::Keycode(KEY_DELETE)],
+        Erasure::Line => {
+            info!("Unable to erase the line because the surrounding text is unknown");
+            Vec::new()
+        }
+    }
+}
+
+/// Get the byte index from where to erase the text left of the cursor and the one until where to erase the text right of the cursor
+fn get_erase_range(erasure: Erasure, left: &str, right: &str) -> (usize, usize) {
+    match erasure {
+        Erasure::PreviousGrapheme => {
+            let grapheme_len = left.graphemes(true).next_back().map_or(0, str::len);
+            (left.len() - grapheme_len, 0)
+        }
+        // Whitespace following the word is erased with it
+        Erasure::PreviousWord => {
+            let trimmed_left = left.trim_end();
+            let word_start = trimmed_left
+                .char_indices()
+                .rev()
+                .find(|(_, character)| character.is_whitespace())
+                .map_or(0, |(index, character)| index + character.len_utf8());
+            (word_start, 0)
+        }
+        Erasure::NextGrapheme => (left.len(), right.graphemes(true).next().map_or(0, str::len)),
+        // The line breaks before and after the line are kept
+        Erasure::Line => (
+            left.rfind('\n').map_or(0, |index| index + 1),
+            right.find('\n').unwrap_or(right.len()),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn erases_line_without_line_breaks() {
+        assert_eq!(get_erase_range(Erasure::Line, "ab\ncd", "ef\ngh"), (3, 2));
+        assert_eq!(get_erase_range(Erasure::Line, "abc", ""), (0, 0));
+    }
+
+    #[test]
+    fn keeps_line_breaks_around_empty_line() {
+        let left = "abc\n";
+        let right = "\ndef";
+        assert_eq!(get_erase_range(Erasure::Line, left, right), (left.len(), 0));
+    }
+
+    #[test]
+    fn does_not_erase_line_without_surrounding_text() {
+        assert!(get_erase_fallback(Erasure::Line).is_empty());
+    }
 }
@@ -0,0 +1,111 @@
+// Imports from other crates
+use std::fs;
+
+// Imports from other modules
+use crate::config::directories;
+
+/// The PunctuationRules describe how the spacing around punctuation is corrected for a language
+/// The rules are plain-text files in the punctuation directory, one per language
+///
+/// Each line of a file contains the name of a rule followed by the punctuation marks it applies to, separated by SPACES.
+/// Empty lines and lines starting with '#' are ignored. The rules are:
+/// - 'attach': The space that was inserted automatically after a word (e.g. after gesture typing) is removed before the punctuation mark
+/// - 'space_after': A space is inserted automatically after the punctuation mark if it was attached to the word
+/// - 'nbsp_before': A non-breaking space is inserted before the punctuation mark (e.g. in French)
+/// - 'double_space': Two spaces in a row are replaced with the punctuation mark followed by a space
+pub struct PunctuationRules {
+    attach: Vec<char>,
+    space_after: Vec<char>,
+    nbsp_before: Vec<char>,
+    double_space: Option<char>,
+}
+
+impl Default for PunctuationRules {
+    /// Without rules for the language, only two spaces are replaced with ". "
+    fn default() -> PunctuationRules {
+        PunctuationRules {
+            attach: Vec::new(),
+            space_after: Vec::new(),
+            nbsp_before: Vec::new(),
+            double_space: Some('.'),
+        }
+    }
+}
+
+impl PunctuationRules {
+    /// Load the rules of the language (e.g. 'fr' loads the file 'fr.txt')
+    /// If they can not be read, the default rules are returned
+    pub fn load(language: &str) -> PunctuationRules {
+        let path =
+            directories::get_absolute_path(directories::PUNCTUATION_DIR_REL).map(|mut path| {
+                path.push(format!("{language}.txt"));
+                path
+            });
+        let content = match path.as_ref().map(fs::read_to_string) {
+            Some(Ok(content)) => content,
+            Some(Err(err)) => {
+                warn!(
+                    "Unable to read the punctuation rules {:?}. Using the default rules. Error description: {}",
+                    path, err
+                );
+                return PunctuationRules::default();
+            }
+            None => return PunctuationRules::default(),
+        };
+        let mut punctuation_rules = PunctuationRules {
+            attach: Vec::new(),
+            space_after: Vec::new(),
+            nbsp_before: Vec::new(),
+            double_space: None,
+        };
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut words = line.split_whitespace();
+            let rule = words.next().unwrap_or_default().trim_end_matches(':');
+            // Only single chars are punctuation marks
+            let punctuation_marks: Vec<char> = words
+                .filter_map(|word| {
+                    let mut chars = word.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(punctuation_mark), None) => Some(punctuation_mark),
+                        _ => None,
+                    }
+                })
+                .collect();
+            match rule {
+                "attach" => punctuation_rules.attach = punctuation_marks,
+                "space_after" => punctuation_rules.space_after = punctuation_marks,
+                "nbsp_before" => punctuation_rules.nbsp_before = punctuation_marks,
+                "double_space" => {
+                    punctuation_rules.double_space = punctuation_marks.first().copied()
+                }
+                _ => warn!("Unknown punctuation rule '{}' in {:?}", rule, path),
+            }
+        }
+        info!("Loaded the punctuation rules {:?}", path);
+        punctuation_rules
+    }
+
+    /// Returns true if the automatically inserted space before the punctuation mark is removed
+    pub fn attaches(&self, punctuation_mark: char) -> bool {
+        self.attach.contains(&punctuation_mark)
+    }
+
+    /// Returns true if a space is inserted after the punctuation mark
+    pub fn has_space_after(&self, punctuation_mark: char) -> bool {
+        self.space_after.contains(&punctuation_mark)
+    }
+
+    /// Returns true if a non-breaking space is inserted before the punctuation mark
+    pub fn has_nbsp_before(&self, punctuation_mark: char) -> bool {
+        self.nbsp_before.contains(&punctuation_mark)
+    }
+
+    /// Returns the punctuation mark two spaces in a row are replaced with
+    pub fn get_double_space(&self) -> Option<char> {
+        self.double_space
+    }
+}
@@ -0,0 +1,65 @@
+// Imports from other crates
+use std::collections::HashMap;
+use std::fs;
+
+// Imports from other modules
+use crate::config::directories;
+
+/// Marker in an expansion where the cursor is placed after the snippet was expanded
+pub const CURSOR_MARKER: &str = "$CURSOR";
+/// Maximum number of chars of an expansion that are shown in the suggestion bar
+const MAX_LABEL_CHARS: usize = 20;
+
+/// The Snippets map triggers to the text they expand to (e.g. 'addr' -> the full address)
+/// They are read from a plain-text file in the users data directory
+///
+/// Each line of the file contains a trigger and its expansion, separated by a TAB. In the expansion, '\n' is a new line and '\t' a TAB.
+/// Empty lines and lines starting with '#' are ignored.
+pub struct Snippets {
+    expansions: HashMap<String, String>,
+}
+
+impl Snippets {
+    /// Load the snippets of the user
+    /// If there is no snippet file, no snippets are available
+    pub fn load() -> Snippets {
+        let mut expansions = HashMap::new();
+        let path = directories::get_absolute_data_path(directories::SNIPPETS_FILE_REL);
+        match path.as_ref().map(fs::read_to_string) {
+            Some(Ok(content)) => {
+                for line in content.lines() {
+                    if line.trim().is_empty() || line.starts_with('#') {
+                        continue;
+                    }
+                    if let Some((trigger, expansion)) = line.split_once('\t') {
+                        let expansion = expansion.replace("\\n", "\n").replace("\\t", "\t");
+                        expansions.insert(trigger.trim().to_string(), expansion);
+                    } else {
+                        warn!("Ignoring a line of the snippet file without a TAB");
+                    }
+                }
+                info!("Loaded {} snippets from {:?}", expansions.len(), path);
+            }
+            Some(Err(_)) => info!("There is no snippet file at {:?}", path),
+            None => error!("Unable to determine the path of the snippet file"),
+        }
+        Snippets { expansions }
+    }
+
+    /// Returns the expansion of the trigger
+    pub fn get(&self, trigger: &str) -> Option<&str> {
+        self.expansions.get(trigger).map(String::as_str)
+    }
+
+    /// Returns the label the snippet of the trigger is suggested with
+    /// It is the beginning of the first line of the expansion
+    pub fn get_label(&self, trigger: &str) -> Option<String> {
+        let expansion = self.get(trigger)?.replace(CURSOR_MARKER, "");
+        let first_line = expansion.lines().next().unwrap_or_default();
+        let mut label: String = first_line.chars().take(MAX_LABEL_CHARS).collect();
+        if label.len() < expansion.len() {
+            label.push('…');
+        }
+        Some(label)
+    }
+}
@@ -0,0 +1,293 @@
+// Imports from other crates
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+// Imports from other modules
+use crate::config::directories;
+use crate::config::input_settings::{LEARN_WORD_THRESHOLD, SAVE_COUNTS_INTERVAL};
+
+/// Number of points both paths are resampled to before they get compared
+const RESAMPLED_POINTS: usize = 32;
+/// Maximum average distance between the drawn path and the ideal path of a learned word to consider the word a match
+const MAX_PATH_DISTANCE: f64 = 0.06;
+
+/// The UserDictionary stores the words the user committed together with how often they were committed.
+/// Words that were committed at least LEARN_WORD_THRESHOLD times are considered learned.
+/// There is one dictionary per language. It is saved as a plain-text file in the users data directory.
+/// Only the learned words are saved. How often the other words were committed is only kept in memory.
+/// The file is written when a word is learned or removed. Changed counts of learned words are saved every SAVE_COUNTS_INTERVAL commits.
+///
+/// Each line of the file contains a word and optionally the number of times it was committed, separated by a TAB.
+/// Words without a number are considered learned. Empty lines and lines starting with '#' are ignored.
+pub struct UserDictionary {
+    path: Option<PathBuf>,
+    words: HashMap<String, u32>,
+    no_unsaved_commits: u32, // Number of commits of learned words since the dictionary was last saved
+}
+
+impl UserDictionary {
+    /// Load the user dictionary of the language
+    /// If it does not exist yet, an empty dictionary is returned. It will be created once the first word is saved
+    pub fn load(language: &str) -> UserDictionary {
+        let path = directories::get_absolute_data_path(directories::USER_DICTIONARY_DIR_REL).map(
+            |mut path| {
+                path.push(format!("{language}.txt"));
+                path
+            },
+        );
+        let mut user_dictionary = UserDictionary {
+            path,
+            words: HashMap::new(),
+            no_unsaved_commits: 0,
+        };
+        if let Some(path) = user_dictionary.path.clone() {
+            if path.exists() {
+                match user_dictionary.read_words_from(&path) {
+                    Ok(no_words) => info!(
+                        "Loaded {} words from the user dictionary {:?}",
+                        no_words, path
+                    ),
+                    Err(err) => error!(
+                        "Unable to read the user dictionary {:?}. Error description: {}",
+                        path, err
+                    ),
+                }
+            } else {
+                info!(
+                    "There is no user dictionary for the language '{}' yet",
+                    language
+                );
+            }
+        } else {
+            error!("Unable to determine the path of the user dictionary. Learned words will not be saved");
+        }
+        user_dictionary
+    }
+
+    /// Notify the dictionary that the word was committed
+    /// Returns 'true' if the word became learned with this commit
+    pub fn record_commit(&mut self, word: &str) -> bool {
+        let count = self.words.entry(word.to_string()).or_insert(0);
+        *count = count.saturating_add(1);
+        let newly_learned = *count == LEARN_WORD_THRESHOLD;
+        if newly_learned {
+            self.save();
+        } else if *count > LEARN_WORD_THRESHOLD {
+            self.no_unsaved_commits += 1;
+            if self.no_unsaved_commits >= SAVE_COUNTS_INTERVAL {
+                self.save();
+            }
+        }
+        newly_learned
+    }
+
+    /// Remove the word from the dictionary
+    /// Returns 'true' if the word was part of the dictionary
+    pub fn remove(&mut self, word: &str) -> bool {
+        let removed = self.words.remove(word).is_some();
+        if removed {
+            self.save();
+        }
+        removed
+    }
+
+    /// Returns 'true' if the word was committed often enough to be learned
+    pub fn is_learned(&self, word: &str) -> bool {
+        self.words
+            .get(word)
+            .is_some_and(|count| *count >= LEARN_WORD_THRESHOLD)
+    }
+
+    /// Returns the learned words that start with the prefix
+    /// The words are ordered by how often they were committed
+    pub fn get_completions(&self, prefix: &str, max_no_words: usize) -> Vec<String> {
+        let mut completions: Vec<(&String, &u32)> = self
+            .words
+            .iter()
+            .filter(|(word, count)| {
+                **count >= LEARN_WORD_THRESHOLD && word.starts_with(prefix) && *word != prefix
+            })
+            .collect();
+        completions.sort_by(|(word_a, count_a), (word_b, count_b)| {
+            count_b.cmp(count_a).then_with(|| word_a.cmp(word_b))
+        });
+        completions
+            .into_iter()
+            .take(max_no_words)
+            .map(|(word, _)| word.to_string())
+            .collect()
+    }
+
+    /// Find the learned words whose ideal path is similar to the drawn path
+    /// The returned words are ordered by the distance of their path to the drawn path
+    pub fn find_similar_words(
+        &self,
+        drawn_path: &[(f64, f64)],
+        key_centers: &HashMap<char, (f64, f64)>,
+    ) -> Vec<(String, f64)> {
+        let mut similar_words: Vec<(String, f64)> = self
+            .words
+            .keys()
+            .filter(|word| self.is_learned(word))
+            .filter_map(|word| {
+                get_path_distance(word, drawn_path, key_centers).map(|dist| (word.clone(), dist))
+            })
+            .filter(|(_, distance)| *distance < MAX_PATH_DISTANCE)
+            .collect();
+        similar_words.sort_by(|(_, dist_a), (_, dist_b)| dist_a.total_cmp(dist_b));
+        similar_words
+    }
+
+    /// Merge the words from a plain-text file into the dictionary
+    /// Returns the number of words that were read
+    pub fn import(&mut self, path: &Path) -> io::Result<usize> {
+        let no_words = self.read_words_from(path)?;
+        self.save();
+        Ok(no_words)
+    }
+
+    /// Write the learned words of the dictionary to a plain-text file
+    pub fn export(&self, path: &Path) -> io::Result<()> {
+        let mut words: Vec<(&String, &u32)> = self
+            .words
+            .iter()
+            .filter(|(_, count)| **count >= LEARN_WORD_THRESHOLD)
+            .collect();
+        words.sort();
+        let mut file = fs::File::create(path)?;
+        for (word, count) in words {
+            writeln!(file, "{word}\t{count}")?;
+        }
+        Ok(())
+    }
+
+    /// Read the words from a plain-text file and add them to the dictionary
+    /// If a word is already known, the higher of both counts is kept
+    fn read_words_from(&mut self, path: &Path) -> io::Result<usize> {
+        let file = fs::File::open(path)?;
+        let mut no_words = 0;
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut columns = line.split('\t');
+            let word = columns.next().unwrap_or_default().trim();
+            if word.is_empty() {
+                continue;
+            }
+            // Words without a valid count are treated as learned
+            let count = columns
+                .next()
+                .and_then(|count| count.trim().parse().ok())
+                .unwrap_or(LEARN_WORD_THRESHOLD);
+            let known_count = self.words.entry(word.to_string()).or_insert(0);
+            *known_count = (*known_count).max(count);
+            no_words += 1;
+        }
+        Ok(no_words)
+    }
+
+    /// Save the learned words to the file of the dictionary in the users data directory
+    fn save(&mut self) {
+        self.no_unsaved_commits = 0;
+        if let Some(path) = &self.path {
+            if let Some(parent_dir) = path.parent() {
+                if let Err(err) = fs::create_dir_all(parent_dir) {
+                    error!(
+                        "Unable to create the directory for the user dictionary. Error description: {}",
+                        err
+                    );
+                    return;
+                }
+            }
+            if let Err(err) = self.export(path) {
+                error!(
+                    "Unable to save the user dictionary {:?}. Error description: {}",
+                    path, err
+                );
+            }
+        }
+    }
+}
+
+/// Calculate the average distance between the drawn path and the ideal path of the word
+/// Returns 'None' if one of the chars of the word is not on the layout
+pub fn get_path_distance(
+    word: &str,
+    drawn_path: &[(f64, f64)],
+    key_centers: &HashMap<char, (f64, f64)>,
+) -> Option<f64> {
+    let ideal_path = get_ideal_path(word, key_centers)?;
+    let ideal_path = resample(&ideal_path);
+    let drawn_path = resample(drawn_path);
+    if ideal_path.is_empty() || drawn_path.is_empty() {
+        return None;
+    }
+    let summed_distance: f64 = ideal_path
+        .iter()
+        .zip(drawn_path.iter())
+        .map(|(point_a, point_b)| get_distance(*point_a, *point_b))
+        .sum();
+    Some(summed_distance / RESAMPLED_POINTS as f64)
+}
+
+/// The ideal path of a word connects the centers of the keys of its chars
+/// Repeated chars are only visited once because the finger does not move
+fn get_ideal_path(word: &str, key_centers: &HashMap<char, (f64, f64)>) -> Option<Vec<(f64, f64)>> {
+    let mut ideal_path: Vec<(f64, f64)> = Vec::new();
+    for character in word.chars().flat_map(char::to_lowercase) {
+        let key_center = *key_centers.get(&character)?;
+        if ideal_path.last() != Some(&key_center) {
+            ideal_path.push(key_center);
+        }
+    }
+    Some(ideal_path)
+}
+
+/// Resample the path to RESAMPLED_POINTS points that are evenly spaced along the path
+fn resample(path: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let first_point = match path.first() {
+        Some(first_point) => *first_point,
+        None => return Vec::new(),
+    };
+    let path_length: f64 = path
+        .windows(2)
+        .map(|segment| get_distance(segment[0], segment[1]))
+        .sum();
+    // A path without a length (e.g. a word with a single letter) consists of one point
+    if path_length == 0.0 {
+        return vec![first_point; RESAMPLED_POINTS];
+    }
+    let step = path_length / (RESAMPLED_POINTS - 1) as f64;
+    let mut resampled_path = vec![first_point];
+    let mut distance_to_next_point = step;
+    for segment in path.windows(2) {
+        let (mut start, end) = (segment[0], segment[1]);
+        let mut segment_length = get_distance(start, end);
+        // Add points as long as the remaining segment is long enough
+        while segment_length >= distance_to_next_point && resampled_path.len() < RESAMPLED_POINTS {
+            let ratio = distance_to_next_point / segment_length;
+            start = (
+                start.0 + ratio * (end.0 - start.0),
+                start.1 + ratio * (end.1 - start.1),
+            );
+            resampled_path.push(start);
+            segment_length -= distance_to_next_point;
+            distance_to_next_point = step;
+        }
+        distance_to_next_point -= segment_length;
+    }
+    // Rounding errors can cause the last point to be missing
+    let last_point = *path.last().unwrap();
+    resampled_path.resize(RESAMPLED_POINTS, last_point);
+    resampled_path
+}
+
+/// Calculate the distance between point A and point B
+fn get_distance(point_a: (f64, f64), point_b: (f64, f64)) -> f64 {
+    ((point_a.0 - point_b.0).powi(2) + (point_a.1 - point_b.1).powi(2)).sqrt()
+}
@@ -1,27 +1,41 @@
 // Imports from other crates
 extern crate pretty_env_logger;
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use wayland_protocols::unstable::text_input::v3::client::zwp_text_input_v3::{
+    ContentHint, ContentPurpose,
+};
 
 // Imports from other modules
 use crate::config::fallback_layout::{FALLBACK_LAYOUT_NAME, FALLBACK_VIEW_NAME};
-use crate::decoder::Decoder;
+use crate::config::input_settings::{
+    CURSOR_MOVEMENT_DISTANCE, FLICK_MAX_DISTANCE, LONG_PRESS_DELAY, MULTI_TAP_TIMEOUT,
+    SWIPE_ERASE_WORD_DISTANCE,
+};
+use crate::decoder::{CursorMovement, Decoder, Erasure};
+use crate::privacy::{self, redact};
 pub use crate::submitter::KeyMotion;
-use crate::submitter::{Submission, Submitter};
+use crate::submitter::{Submission, Submitter, KEY_BACKSPACE};
 use crate::user_interface::Msg;
 
 // Modules
 mod content_connector;
+mod hangul;
 mod key;
 mod meta;
+mod pinyin;
 mod ui_connector;
 mod view;
+use hangul::HangulComposer;
 use key::Key;
+use pinyin::PinyinComposer;
 use view::View;
 
 // Re-exports
 pub use self::meta::{
-    KeyAction, KeyArrangement, KeyDisplay, KeyMeta, LayoutMeta, Location, Modifier,
+    InputEngine, KeyAction, KeyArrangement, KeyDisplay, KeyMeta, LayoutMeta, Location, Modifier,
+    SequenceStep,
 };
 pub use ui_connector::UIConnector;
 
@@ -40,6 +54,12 @@ pub enum Interaction {
     Tap(TapDuration, TapMotion),
     /// A swipe is when the user touches the UI and moves her finger around
     Swipe(SwipeAction),
+    /// A flick is a short swipe that stays near the key it started on
+    Flick(FlickDirection),
+    /// A double tap is when a short tap ends shortly after a previous tap at the same place. It replaces the release of the second tap
+    DoubleTap,
+    /// The touch was interrupted (e.g. by the compositor or because the keyboard was hidden). Nothing is submitted
+    Cancel,
 }
 impl std::fmt::Display for Interaction {
     /// Makes the Interaction nicely printable
@@ -52,6 +72,12 @@ impl std::fmt::Display for Interaction {
             Interaction::Swipe(SwipeAction::Begin) => write!(f, "SwipeBegin"),
             Interaction::Swipe(SwipeAction::Update) => write!(f, "SwipeUpdate"),
             Interaction::Swipe(SwipeAction::Finish) => write!(f, "SwipeFinish"),
+            Interaction::Flick(FlickDirection::Up) => write!(f, "FlickUp"),
+            Interaction::Flick(FlickDirection::Down) => write!(f, "FlickDown"),
+            Interaction::Flick(FlickDirection::Left) => write!(f, "FlickLeft"),
+            Interaction::Flick(FlickDirection::Right) => write!(f, "FlickRight"),
+            Interaction::DoubleTap => write!(f, "DoubleTap"),
+            Interaction::Cancel => write!(f, "Cancel"),
         }
     }
 }
@@ -70,6 +96,70 @@ pub enum SwipeAction {
     Finish,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Direction of a flick
+pub enum FlickDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Motion of a single finger. Layouts in direct mode are controlled by the touches of each finger instead of interactions
+pub enum TouchMotion {
+    Begin,
+    Update,
+    End,
+    Cancel,
+}
+
+/// A swipe that started on a key with actions for flicks. It is a flick if the finger stays near the key
+struct FlickCandidate {
+    key: Key,
+    start: (f64, f64),
+    left_start_key: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// When letters are capitalized automatically. This is requested by the content hint of the text input
+enum AutoCapitalization {
+    Off,
+    Sentences,
+    Words,
+    All,
+}
+
+/// A touch while several fingers type at once
+/// Its key is committed when the touch ended and all touches that began before it were committed
+struct RolloverTouch {
+    sequence: usize,
+    key: Option<Key>,
+    ended: bool,
+}
+
+/// The character of a multi-tap key that is shown as preedit until it is committed
+struct MultiTap {
+    key_id: String,
+    characters: Vec<String>,
+    index: usize,
+    last_tap: Instant,
+}
+
+/// Keeps track of the words that were erased by swiping left on the BackSpace key
+/// The erasure of a word is undone when the finger slides back
+struct SwipeErase {
+    start_x: f64,
+    erased_words: Vec<Option<String>>, // 'None' if the erased word is unknown and can't be restored
+}
+
+/// Keeps track of how far the cursor was moved by dragging the finger sideways on the space key
+struct CursorDrag {
+    start_x: f64,
+    moved_steps: i64, // Negative if the cursor was moved to the left
+    extend_selection: bool,
+}
+
 /// The keyboard struct is the "heart" of the application. It is the connector between the Decoder and the Submitter.
 /// It also stores the available views, which contain the keys. It is the keyboards job to find out which key the user wanted to press,
 /// Decode the keypress and notify the UI and Submitter, if they need to take action. The keyboard also saves which layout/view it was set to before,
@@ -79,10 +169,32 @@ pub struct Keyboard {
     pub active_view: (String, String),
     latched_keys: HashSet<String>,
     active_key: Option<Key>,
+    active_key_position: (f64, f64), // Position of the interaction that pressed the active key
+    active_key_long_pressed: bool,
+    multiple_touches: bool, // True while more than one finger touches the keyboard
     layout_of_active_key: String, // Necessary to remember to release the key on the correct layout after a switch of the layout
     view_of_active_key: String, // Necessary to remember to release the key on the correct view after a switch of the view
     next_layout: Option<String>,
     next_view: Option<String>,
+    last_gesture_commit: Option<String>, // Text that was committed for the last gesture. It is erased as a whole by the next BackSpace
+    swipe_erase: Option<SwipeErase>,     // Only 'Some' while swiping on the BackSpace key
+    cursor_drag: Option<CursorDrag>, // Only 'Some' while dragging the finger sideways on the space key
+    flick_candidate: Option<FlickCandidate>, // Only 'Some' while swiping from a key with actions for flicks
+    direct_layouts: HashSet<String>,         // Names of the layouts in direct mode
+    sticky_modifier_layouts: HashSet<String>, // Names of the layouts that release the latched modifiers after the next key
+    shifted_views: HashMap<String, String>, // Names of the layouts with automatic capitalization and the names of their shifted views
+    input_engines: HashMap<String, InputEngine>, // Names of the layouts that compose the entered strings and the engine they use
+    hangul_composer: HangulComposer,
+    pinyin_composer: Option<PinyinComposer>, // Only 'Some' if a layout uses pinyin and its dictionary was loaded
+    auto_capitalization: AutoCapitalization,
+    auto_shifted: bool, // True while the shifted view is shown because the next letter is capitalized automatically
+    direct_presses: HashMap<usize, Key>, // Keys that are held down in direct mode by each touch sequence
+    primary_touch: Option<(usize, Option<Key>)>, // The touch that began while no other finger touched the keyboard and the key under it
+    rollover_touches: Vec<RolloverTouch>, // Touches in the order they began. Only not empty while several fingers type at once
+    multi_tap: Option<MultiTap>, // Only 'Some' while the character of a multi-tap key is shown as preedit
+    repeating_key: Option<Key>,  // Only 'Some' while a key that repeats is held down
+    pending_sequence: Option<(usize, Key, Vec<SequenceStep>)>, // The number of the delay, the key and the remaining steps of a sequence that waits for the delay to pass
+    no_sequence_delays: usize, // Numbers the delays, so the timer of a replaced sequence does not continue the new one
     ui_connection: UIConnector, // Allows sending messages to the UI
     decoder: Decoder,
     submitter: Submitter<ui_connector::UIConnector, content_connector::ContentConnector>,
@@ -91,13 +203,15 @@ pub struct Keyboard {
 impl Keyboard {
     /// Reads the layout infos and builds a keyboard struct from it
     pub fn from(
-        ui_connector: ui_connector::UIConnector,
+        mut ui_connector: ui_connector::UIConnector,
         layout_meta_hashmap: &HashMap<String, LayoutMeta>,
     ) -> Keyboard {
         // Creates a new submitter and moves a clone of the ui_connector to it
         let ui_connection = ui_connector.clone();
         // Create a new channel. This will be used to send changes of the surrounding text to the decoder
         let (tx, rx) = mpsc::channel();
+        // The input_method service tells the ui_connector about focus changes, which make the surrounding text unknown
+        ui_connector.set_content_transmitter(tx.clone());
         // Create a new decoder that stores the receiver of the channel
         let decoder = Decoder::new(ui_connection.clone(), rx);
         // Create a new connection to allow the input_method protocol to notify the keyboard about changes to the surrounding text
@@ -108,8 +222,24 @@ impl Keyboard {
         // Create a view for each 'KeyArrangement'
         let mut views = HashMap::new();
         let mut layout_names = HashSet::new();
+        let mut direct_layouts = HashSet::new();
+        let mut sticky_modifier_layouts = HashSet::new();
+        let mut shifted_views = HashMap::new();
+        let mut input_engines = HashMap::new();
         for (layout_name, layout_meta) in layout_meta_hashmap {
             layout_names.insert(layout_name.to_string());
+            if layout_meta.direct {
+                direct_layouts.insert(layout_name.to_string());
+            }
+            if layout_meta.sticky_modifiers {
+                sticky_modifier_layouts.insert(layout_name.to_string());
+            }
+            if let Some(shifted_view) = &layout_meta.shifted_view {
+                shifted_views.insert(layout_name.to_string(), shifted_view.to_string());
+            }
+            if let Some(input_engine) = layout_meta.input_engine {
+                input_engines.insert(layout_name.to_string(), input_engine);
+            }
             for (view_name, key_arrangement) in &layout_meta.views {
                 let view = View::from(key_arrangement, &layout_meta.keys);
 
@@ -122,6 +252,16 @@ impl Keyboard {
         }
         views.shrink_to_fit();
 
+        // The dictionary of the pinyin is only loaded if it is needed
+        let pinyin_composer = if input_engines
+            .values()
+            .any(|input_engine| *input_engine == InputEngine::Pinyin)
+        {
+            PinyinComposer::load()
+        } else {
+            None
+        };
+
         // Select a layout and view to start with
         let active_view = Keyboard::get_start_layout_view(layout_names);
 
@@ -137,10 +277,32 @@ impl Keyboard {
             active_view,
             latched_keys: HashSet::new(),
             active_key: None,
+            active_key_position: (0.0, 0.0),
+            active_key_long_pressed: false,
+            multiple_touches: false,
             layout_of_active_key,
             view_of_active_key,
             next_layout: None,
             next_view: None,
+            last_gesture_commit: None,
+            swipe_erase: None,
+            cursor_drag: None,
+            flick_candidate: None,
+            direct_layouts,
+            sticky_modifier_layouts,
+            shifted_views,
+            input_engines,
+            hangul_composer: HangulComposer::new(),
+            pinyin_composer,
+            auto_capitalization: AutoCapitalization::Off,
+            auto_shifted: false,
+            direct_presses: HashMap::new(),
+            primary_touch: None,
+            rollover_touches: Vec::new(),
+            multi_tap: None,
+            repeating_key: None,
+            pending_sequence: None,
+            no_sequence_delays: 0,
             ui_connection,
             decoder,
             submitter,
@@ -166,15 +328,294 @@ impl Keyboard {
     /// the UI get notified when they need to take action
     pub fn input(&mut self, x: f64, y: f64, interaction: Interaction) {
         let (x, y) = self.get_idealized_coordinate(x, y);
-        info!("Keyboard handles {} at x: {}, y: {}", interaction, x, y);
+        info!(
+            "Keyboard handles {} at x: {}, y: {}",
+            interaction,
+            redact(x),
+            redact(y)
+        );
+        // A key stops repeating as soon as the finger is lifted or moved
+        if self.repeating_key.is_some()
+            && interaction != Interaction::Tap(TapDuration::Long, TapMotion::Press)
+        {
+            self.stop_key_repeat();
+        }
         // Differentiate between a tap and a swipe
         match interaction {
-            Interaction::Tap(_, _) => {
+            Interaction::Tap(_, _) | Interaction::Flick(_) | Interaction::DoubleTap => {
                 self.handle_tap(x, y, interaction);
             }
             Interaction::Swipe(swipe_action) => {
                 self.handle_swipe(x, y, swipe_action);
             }
+            Interaction::Cancel => {
+                self.handle_cancel();
+            }
+        }
+    }
+
+    /// Handle an Interaction::Cancel
+    /// The pressed keys are released without executing their actions and the gesture is forgotten
+    fn handle_cancel(&mut self) {
+        // Release the buttons of the pressed keys. Latched keys stay latched
+        let mut pressed_key_ids: Vec<String> = self
+            .rollover_touches
+            .drain(..)
+            .filter_map(|touch| touch.key)
+            .chain(self.active_key.take())
+            .map(|key| key.get_id())
+            .collect();
+        pressed_key_ids.retain(|key_id| !self.latched_keys.contains(key_id));
+        for key_id in pressed_key_ids {
+            self.ui_connection.emit(Msg::ButtonInteraction(
+                self.layout_of_active_key.to_string(),
+                self.view_of_active_key.to_string(),
+                key_id,
+                TapMotion::Release,
+            ));
+        }
+        let direct_presses: Vec<Key> = self.direct_presses.drain().map(|(_, key)| key).collect();
+        for key in direct_presses {
+            self.release_direct_keycodes(&key);
+        }
+        self.primary_touch = None;
+        self.active_key_long_pressed = false;
+        // Forget the swipe without submitting anything
+        self.swipe_erase = None;
+        self.cursor_drag = None;
+        self.flick_candidate = None;
+        self.decoder.cancel_gesture();
+        // The remaining steps of a sequence are not executed
+        self.pending_sequence = None;
+        self.repeating_key = None;
+        // Release the keycodes that were toggled
+        self.submitter.release_all_keys();
+    }
+
+    /// Returns true if the active layout is in direct mode
+    /// In direct mode, the keyboard needs to be told about the touches of each finger instead of interactions
+    pub fn is_direct(&self) -> bool {
+        self.direct_layouts.contains(&self.active_view.0)
+    }
+
+    /// Tell the keyboard about the motion of a finger on a layout in direct mode
+    /// Touching a key presses it and lifting the finger releases it. If the finger slides to a different key, the press moves to that key
+    /// Each finger is identified by its touch sequence, so several keys can be held at once
+    pub fn input_touch(&mut self, sequence: usize, x: f64, y: f64, touch_motion: TouchMotion) {
+        let (x, y) = self.get_idealized_coordinate(x, y);
+        info!(
+            "Keyboard handles touch {:?} of sequence {} at x: {}, y: {}",
+            touch_motion,
+            sequence,
+            redact(x),
+            redact(y)
+        );
+        if !self.is_direct() {
+            // Keys that are still held after switching away from a layout in direct mode are released without executing their actions
+            if let Some(key) = self.direct_presses.remove(&sequence) {
+                self.release_direct_keycodes(&key);
+            }
+            self.handle_rollover_touch(sequence, x, y, touch_motion);
+            return;
+        }
+        match touch_motion {
+            TouchMotion::Begin | TouchMotion::Update => {
+                let key = self
+                    .views
+                    .get(&self.active_view)
+                    .and_then(|view| view.get_closest_key(x, y))
+                    .cloned();
+                let pressed_key_id = self.direct_presses.get(&sequence).map(Key::get_id);
+                if key.as_ref().map(Key::get_id) != pressed_key_id {
+                    self.release_direct(sequence);
+                    if let Some(key) = key {
+                        self.press_direct(sequence, key);
+                    }
+                }
+            }
+            TouchMotion::End => self.release_direct(sequence),
+            // A canceled touch must not trigger the actions of keys without keycodes
+            TouchMotion::Cancel => {
+                if let Some(key) = self.direct_presses.remove(&sequence) {
+                    self.release_direct_keycodes(&key);
+                }
+            }
+        }
+    }
+
+    /// Returns true while several fingers type at once
+    /// The keyboard then commits the key of each touch itself and drags are not interpreted as swipes
+    pub fn is_rolling_over(&self) -> bool {
+        !self.rollover_touches.is_empty()
+    }
+
+    /// Keep track of the touches on layouts that are not in direct mode
+    /// A single touch is handled with the interactions of the drag. When a second finger touches the keyboard before the first one is lifted,
+    /// the keys of all touches are committed in the order the touches began
+    fn handle_rollover_touch(
+        &mut self,
+        sequence: usize,
+        x: f64,
+        y: f64,
+        touch_motion: TouchMotion,
+    ) {
+        match touch_motion {
+            TouchMotion::Begin => {
+                let key = self
+                    .views
+                    .get(&self.active_view)
+                    .and_then(|view| view.get_closest_key(x, y))
+                    .cloned();
+                if self.is_rolling_over() {
+                    self.press_rollover_touch(sequence, key);
+                } else if self.primary_touch.is_none() {
+                    self.primary_touch = Some((sequence, key));
+                } else if self.can_roll_over() {
+                    info!("A second finger touches the keyboard. The keys of the touches are committed in order");
+                    if let Some((primary_sequence, primary_key)) = self.primary_touch.take() {
+                        self.rollover_touches.push(RolloverTouch {
+                            sequence: primary_sequence,
+                            key: primary_key,
+                            ended: false,
+                        });
+                    }
+                    // The end of the drag must not release the key of the first touch a second time
+                    self.active_key = None;
+                    self.press_rollover_touch(sequence, key);
+                }
+            }
+            TouchMotion::Update => {}
+            TouchMotion::End => {
+                if self
+                    .primary_touch
+                    .as_ref()
+                    .map(|(primary_sequence, _)| *primary_sequence)
+                    == Some(sequence)
+                {
+                    self.primary_touch = None;
+                }
+                if let Some(touch) = self
+                    .rollover_touches
+                    .iter_mut()
+                    .find(|touch| touch.sequence == sequence)
+                {
+                    touch.ended = true;
+                }
+                // Commit the keys of the ended touches unless a touch that began earlier is still on the keyboard
+                while self
+                    .rollover_touches
+                    .first()
+                    .map_or(false, |touch| touch.ended)
+                {
+                    let touch = self.rollover_touches.remove(0);
+                    if let Some(key) = touch.key {
+                        self.execute_tap_actions(
+                            &key,
+                            Interaction::Tap(TapDuration::Short, TapMotion::Release),
+                        );
+                    }
+                }
+            }
+            // A canceled touch does not commit its key
+            TouchMotion::Cancel => {
+                if self
+                    .primary_touch
+                    .as_ref()
+                    .map(|(primary_sequence, _)| *primary_sequence)
+                    == Some(sequence)
+                {
+                    self.primary_touch = None;
+                }
+                if let Some(index) = self
+                    .rollover_touches
+                    .iter()
+                    .position(|touch| touch.sequence == sequence)
+                {
+                    if let Some(key) = self.rollover_touches.remove(index).key {
+                        let (layout, view) = self.active_view.clone();
+                        self.ui_connection.emit(Msg::ButtonInteraction(
+                            layout,
+                            view,
+                            key.get_id(),
+                            TapMotion::Release,
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns true if the first touch is a tap that can be committed together with the taps of other fingers
+    /// A swipe, a long press or a drag on the space key (a second finger extends the selection) are continued instead
+    fn can_roll_over(&self) -> bool {
+        self.active_key
+            .as_ref()
+            .map_or(false, |active_key| !active_key.is_space())
+            && !self.active_key_long_pressed
+            && self.swipe_erase.is_none()
+            && self.cursor_drag.is_none()
+            && self.flick_candidate.is_none()
+    }
+
+    /// Press the key of a touch while several fingers type at once
+    fn press_rollover_touch(&mut self, sequence: usize, key: Option<Key>) {
+        if let Some(key) = &key {
+            let (layout_of_active_key, view_of_active_key) = self.active_view.clone();
+            self.layout_of_active_key = layout_of_active_key;
+            self.view_of_active_key = view_of_active_key;
+            self.execute_tap_actions(key, Interaction::Tap(TapDuration::Short, TapMotion::Press));
+        }
+        self.rollover_touches.push(RolloverTouch {
+            sequence,
+            key,
+            ended: false,
+        });
+    }
+
+    /// Press the keycodes of the key and keep them pressed until the sequence ends
+    fn press_direct(&mut self, sequence: usize, key: Key) {
+        let (layout, view) = self.active_view.clone();
+        self.ui_connection.emit(Msg::ButtonInteraction(
+            layout,
+            view,
+            key.get_id(),
+            TapMotion::Press,
+        ));
+        for keycode in key.get_keycodes() {
+            self.submitter
+                .submit(Submission::KeyMotion(keycode, KeyMotion::Press));
+        }
+        self.direct_presses.insert(sequence, key);
+    }
+
+    /// Release the key that is held down by the sequence
+    fn release_direct(&mut self, sequence: usize) {
+        if let Some(key) = self.direct_presses.remove(&sequence) {
+            self.release_direct_keycodes(&key);
+            // Keys without keycodes (e.g. to switch the layout) execute their actions when they are released
+            if key.get_keycodes().is_empty() {
+                // The end of the drag must not release a key that was pressed before the layout was switched
+                self.active_key = None;
+                self.execute_tap_actions(
+                    &key,
+                    Interaction::Tap(TapDuration::Short, TapMotion::Release),
+                );
+            }
+        }
+    }
+
+    /// Release the keycodes of a key that was pressed in direct mode
+    fn release_direct_keycodes(&mut self, key: &Key) {
+        let (layout, view) = self.active_view.clone();
+        self.ui_connection.emit(Msg::ButtonInteraction(
+            layout,
+            view,
+            key.get_id(),
+            TapMotion::Release,
+        ));
+        for keycode in key.get_keycodes() {
+            self.submitter
+                .submit(Submission::KeyMotion(keycode, KeyMotion::Release));
         }
     }
 
@@ -193,6 +634,8 @@ impl Keyboard {
                 .get_closest_key(x, y)
                 .cloned();
             info!("Keyboard looked up closest key");
+            self.active_key_position = (x, y);
+            self.active_key_long_pressed = false;
             // Remember which layout and view the key was on to be able to release the correct key later on
             let (layout_of_active_key, view_of_active_key) = self.active_view.clone();
             self.layout_of_active_key = layout_of_active_key;
@@ -200,6 +643,9 @@ impl Keyboard {
             &self.active_key
         } else {
             info!("Keyboard did not look up the closest key, but used the previously pressed key");
+            if let Interaction::Tap(TapDuration::Long, TapMotion::Press) = interaction {
+                self.active_key_long_pressed = true;
+            }
             &self.active_key
         };
 
@@ -207,8 +653,77 @@ impl Keyboard {
         if let Some(key) = key {
             // .. execute its actions
             let key = key.clone();
+            // Keys without actions for a double tap handle it like the release of a short tap
+            let interaction = if interaction == Interaction::DoubleTap
+                && key.get_actions(Interaction::DoubleTap).is_none()
+            {
+                Interaction::Tap(TapDuration::Short, TapMotion::Release)
+            } else {
+                interaction
+            };
             self.execute_tap_actions(&key, interaction);
+            // Keys that repeat start repeating their short press once they are held long enough
+            if interaction == Interaction::Tap(TapDuration::Long, TapMotion::Press) && key.repeats()
+            {
+                info!("Key {} repeats while it is held", redact(key.get_id()));
+                self.repeating_key = Some(key);
+                self.repeat_key();
+            }
+        }
+    }
+
+    /// Returns true while a key that repeats is held down
+    pub fn is_repeating_key(&self) -> bool {
+        self.repeating_key.is_some()
+    }
+
+    /// Execute the short press of the key that is held down once more
+    /// Returns false if no key is repeating, so the UI stops asking to repeat it
+    pub fn repeat_key(&mut self) -> bool {
+        let key = if let Some(key) = self.repeating_key.clone() {
+            key
+        } else {
+            return false;
+        };
+        if let Some(action_vec) =
+            key.get_actions(Interaction::Tap(TapDuration::Short, TapMotion::Release))
+        {
+            for action in action_vec
+                .iter()
+                .filter(|action| !matches!(action, KeyAction::FeedbackPressRelease(_)))
+            {
+                self.execute_action(&key, action);
+            }
+        }
+        true
+    }
+
+    /// Stop repeating the key that was held down
+    /// The repetitions count as the next key, so sticky modifiers are released
+    fn stop_key_repeat(&mut self) {
+        self.repeating_key = None;
+        if self
+            .sticky_modifier_layouts
+            .contains(&self.layout_of_active_key)
+        {
+            self.release_latched_modifiers();
+        }
+    }
+
+    /// Release the latched modifiers and the buttons that show them as pressed
+    fn release_latched_modifiers(&mut self) {
+        if self.latched_keys.is_empty() {
+            return;
+        }
+        info!("Releasing the latched modifiers");
+        for key_id in self.latched_keys.drain() {
+            self.ui_connection.emit(Msg::LatchButton(
+                self.layout_of_active_key.to_string(),
+                key_id,
+                false,
+            ));
         }
+        self.submitter.release_all_modifiers();
     }
 
     /// Handle swipe interactions
@@ -232,6 +747,41 @@ impl Keyboard {
                         TapMotion::Release,
                     ));
                 }
+                // Swiping left on the BackSpace key erases words instead of entering a word with a gesture
+                if self.active_key.as_ref().map_or(false, Key::is_backspace) {
+                    info!("Swipe started on the BackSpace key. Words will be erased");
+                    self.swipe_erase = Some(SwipeErase {
+                        start_x: self.active_key_position.0,
+                        erased_words: Vec::new(),
+                    });
+                }
+                // Dragging the finger sideways on the space key moves the cursor instead of entering a word with a gesture
+                // If the key was long pressed before or a second finger touches the keyboard, the selection is extended
+                if self.active_key.as_ref().map_or(false, Key::is_space) {
+                    let extend_selection = self.active_key_long_pressed || self.multiple_touches;
+                    info!(
+                        "Swipe started on the space key. The cursor will be moved (extending the selection: {})",
+                        extend_selection
+                    );
+                    self.cursor_drag = Some(CursorDrag {
+                        start_x: self.active_key_position.0,
+                        moved_steps: 0,
+                        extend_selection,
+                    });
+                }
+                // A short swipe from a key with actions for flicks might be a flick
+                if let Some(active_key) = &self.active_key {
+                    if self.swipe_erase.is_none()
+                        && self.cursor_drag.is_none()
+                        && active_key.has_flick_actions()
+                    {
+                        self.flick_candidate = Some(FlickCandidate {
+                            key: active_key.clone(),
+                            start: self.active_key_position,
+                            left_start_key: false,
+                        });
+                    }
+                }
                 if let Some(active_key) = &self.active_key {
                     self.ui_connection.emit(Msg::ButtonInteraction(
                         layout_of_active_key,
@@ -243,119 +793,744 @@ impl Keyboard {
                 }
                 // .. and also tell the submitter to release all keys and modifiers
                 self.submitter.release_all_keys_and_modifiers();
+                // The decoder needs to know where the keys are to recognize learned words
+                if let Some(active_view) = self.views.get(&self.active_view) {
+                    self.decoder.set_key_centers(active_view.get_key_centers());
+                }
             }
+            // Erases or restores words if the swipe started on the BackSpace key
+            SwipeAction::Update if self.swipe_erase.is_some() => self.update_swipe_erase(x),
+            // Moves the cursor if the swipe started on the space key
+            SwipeAction::Update if self.cursor_drag.is_some() => self.update_cursor_drag(x),
             // Tells decoder to update calculations for gesture recognition
-            SwipeAction::Update => self.decoder.update_gesture(x, y),
+            SwipeAction::Update => {
+                if let Some(flick_candidate) = &mut self.flick_candidate {
+                    let (start_x, start_y) = flick_candidate.start;
+                    flick_candidate.left_start_key |=
+                        (x - start_x).hypot(y - start_y) > FLICK_MAX_DISTANCE;
+                }
+                self.decoder.update_gesture(x, y);
+            }
+            // Ends erasing words. The path is never handed to the decoder
+            SwipeAction::Finish if self.swipe_erase.is_some() => {
+                self.update_swipe_erase(x);
+                self.swipe_erase = None;
+            }
+            // Ends moving the cursor. The path is never handed to the decoder
+            SwipeAction::Finish if self.cursor_drag.is_some() => {
+                self.update_cursor_drag(x);
+                self.cursor_drag = None;
+            }
+            // Executes the actions of the flick if the finger stayed near the key
+            SwipeAction::Finish if self.is_flick(x, y) => {
+                if let Some(flick_candidate) = self.flick_candidate.take() {
+                    let (start_x, start_y) = flick_candidate.start;
+                    let interaction =
+                        Interaction::Flick(get_flick_direction(x - start_x, y - start_y));
+                    info!("Swipe was a flick");
+                    self.decoder.cancel_gesture();
+                    self.execute_tap_actions(&flick_candidate.key, interaction);
+                }
+            }
             // Submits the most likely word
             SwipeAction::Finish => {
+                self.flick_candidate = None;
                 let text = self.decoder.get_gesture_result(x, y);
+                self.last_gesture_commit = if text.is_empty() {
+                    None
+                } else {
+                    Some(format!("{text} "))
+                };
                 self.submit_text(text, true);
             }
         }
     }
 
+    /// Returns true if the swipe that finishes at the coordinates is a flick
+    /// A flick needs to stay near the key it started on and the key needs an action for the direction of the flick
+    fn is_flick(&self, x: f64, y: f64) -> bool {
+        if let Some(flick_candidate) = &self.flick_candidate {
+            let (start_x, start_y) = flick_candidate.start;
+            let (dx, dy) = (x - start_x, y - start_y);
+            !flick_candidate.left_start_key
+                && dx.hypot(dy) <= FLICK_MAX_DISTANCE
+                && flick_candidate
+                    .key
+                    .get_actions(Interaction::Flick(get_flick_direction(dx, dy)))
+                    .is_some()
+        } else {
+            false
+        }
+    }
+
+    /// Erase or restore words depending on how far the finger moved left since the swipe on the BackSpace key started
+    /// One word is erased each time the finger moves another SWIPE_ERASE_WORD_DISTANCE to the left
+    /// When the finger slides back, the erased words are restored one by one
+    fn update_swipe_erase(&mut self, x: f64) {
+        let swipe_erase = if let Some(swipe_erase) = &mut self.swipe_erase {
+            swipe_erase
+        } else {
+            return;
+        };
+        let no_words = ((swipe_erase.start_x - x) / SWIPE_ERASE_WORD_DISTANCE)
+            .floor()
+            .max(0.0) as usize;
+        // Erase words until enough words are erased
+        while swipe_erase.erased_words.len() < no_words {
+            let mut erased_word = None;
+            for submission in self.decoder.decode_erase(Erasure::PreviousWord) {
+                if let Submission::Erase(text) = &submission {
+                    erased_word = Some(text.to_string());
+                }
+                self.submitter.submit(submission);
+            }
+            swipe_erase.erased_words.push(erased_word);
+        }
+        // Restore words until only the number of words is erased
+        while swipe_erase.erased_words.len() > no_words {
+            if let Some(erased_word) = swipe_erase.erased_words.pop().flatten() {
+                let submission = self.decoder.restore_erased_text(erased_word);
+                self.submitter.submit(submission);
+            } else {
+                warn!("The erased word is unknown and can not be restored");
+            }
+        }
+    }
+
+    /// Move the cursor depending on how far the finger moved sideways since the drag on the space key started
+    /// The cursor is moved by one char each time the finger moves another CURSOR_MOVEMENT_DISTANCE
+    fn update_cursor_drag(&mut self, x: f64) {
+        let cursor_drag = if let Some(cursor_drag) = &mut self.cursor_drag {
+            cursor_drag
+        } else {
+            return;
+        };
+        let target_steps = ((x - cursor_drag.start_x) / CURSOR_MOVEMENT_DISTANCE).trunc() as i64;
+        while cursor_drag.moved_steps != target_steps {
+            let movement = if target_steps < cursor_drag.moved_steps {
+                cursor_drag.moved_steps -= 1;
+                CursorMovement::Left
+            } else {
+                cursor_drag.moved_steps += 1;
+                CursorMovement::Right
+            };
+            if let Some(submission) = self
+                .decoder
+                .decode_cursor_movement(movement, cursor_drag.extend_selection)
+            {
+                self.submitter.submit(submission);
+            }
+        }
+    }
+
+    /// Returns the milliseconds the active key needs to be held for a long press
+    pub fn get_long_press_delay(&self) -> u64 {
+        self.active_key
+            .as_ref()
+            .and_then(Key::get_long_press_delay)
+            .unwrap_or(LONG_PRESS_DELAY)
+    }
+
+    /// Tell the keyboard if more than one finger touches it
+    /// A second finger makes dragging on the space key extend the selection
+    pub fn set_multiple_touches(&mut self, multiple_touches: bool) {
+        self.multiple_touches = multiple_touches;
+        if let Some(cursor_drag) = &mut self.cursor_drag {
+            cursor_drag.extend_selection |= multiple_touches;
+        }
+    }
+
     /// Update the suggestions
     pub fn update_suggestions(&self) {
         self.decoder.update_suggestions();
     }
 
+    /// Remove the word from the user dictionary
+    pub fn forget_word(&mut self, word: &str) {
+        self.decoder.forget_word(word);
+    }
+
+    /// Handle a change of the content hint and content purpose of the text input
+    /// Sensitive fields (e.g. password fields) activate the privacy mode. Typed text is redacted from the logs and words are neither learned nor suggested
+    pub fn change_hint_purpose(
+        &mut self,
+        content_hint: ContentHint,
+        content_purpose: ContentPurpose,
+    ) {
+        let is_sensitive = content_hint
+            .intersects(ContentHint::SensitiveData | ContentHint::HiddenText)
+            || matches!(
+                content_purpose,
+                ContentPurpose::Password | ContentPurpose::Pin
+            );
+        privacy::set_sensitive_field(is_sensitive);
+        // A space that was inserted automatically in the previous text field can not be removed in this one
+        self.decoder.reset_auto_space();
+        // Clear the suggestions when a sensitive field is focused and restore them when it is left
+        #[cfg(feature = "suggestions")]
+        self.decoder.update_suggestions();
+        // Punctuation has a different meaning in URLs, email addresses and terminals
+        self.decoder.set_punctuation_rules_enabled(!matches!(
+            content_purpose,
+            ContentPurpose::Url | ContentPurpose::Email | ContentPurpose::Terminal
+        ));
+        // Letters are only capitalized automatically if the text input requests it
+        self.auto_capitalization = if is_sensitive || content_hint.contains(ContentHint::Lowercase)
+        {
+            AutoCapitalization::Off
+        } else if content_hint.contains(ContentHint::Uppercase) {
+            AutoCapitalization::All
+        } else if content_hint.contains(ContentHint::Titlecase) {
+            AutoCapitalization::Words
+        } else if content_hint.contains(ContentHint::AutoCapitalization) {
+            AutoCapitalization::Sentences
+        } else {
+            AutoCapitalization::Off
+        };
+        info!(
+            "Automatic capitalization changed to {:?}",
+            self.auto_capitalization
+        );
+        if let Some(text_left_of_cursor) = self.decoder.get_current_text_left_of_cursor() {
+            self.update_auto_capitalization(&text_left_of_cursor);
+        }
+    }
+
+    /// Switch to the shifted view of the layout if the next letter is capitalized automatically and back to the base view if it no longer is
+    /// The shifted view is only shown for a single letter
+    fn update_auto_capitalization(&mut self, text_left_of_cursor: &str) {
+        let (layout, view) = self.active_view.clone();
+        let shifted_view = if let Some(shifted_view) = self.shifted_views.get(&layout) {
+            shifted_view.to_string()
+        } else {
+            return;
+        };
+        let capitalize = match self.auto_capitalization {
+            AutoCapitalization::Off => false,
+            AutoCapitalization::Sentences => is_sentence_start(text_left_of_cursor),
+            AutoCapitalization::Words => {
+                text_left_of_cursor.is_empty() || text_left_of_cursor.ends_with(char::is_whitespace)
+            }
+            AutoCapitalization::All => true,
+        };
+        if capitalize && view == FALLBACK_VIEW_NAME {
+            info!("Switching to the shifted view to capitalize the next letter");
+            self.switch_layout(None, Some(shifted_view), true);
+            self.auto_shifted = true;
+        } else if !capitalize && self.auto_shifted && view == shifted_view {
+            info!(
+                "Switching back to the base view because the next letter is no longer capitalized"
+            );
+            self.switch_back_to_prev_view();
+        }
+    }
+
     /// Execute the actions that the key causes when it is tapped
     /// EnterString actions get decoded before they get submitted
     fn execute_tap_actions(&mut self, key: &Key, interaction: Interaction) {
-        info!("Keyboard handles actions for key {}", key.get_id());
+        info!("Keyboard handles actions for key {}", redact(key.get_id()));
+
+        // The character of a multi-tap key is committed when a different key is used
+        if self
+            .multi_tap
+            .as_ref()
+            .map_or(false, |multi_tap| multi_tap.key_id != key.get_id())
+        {
+            self.commit_multi_tap();
+        }
 
         // Switch back to the previous layout/view
         self.switch_back_to_prev_view();
+        self.auto_shifted = false;
 
         if let Some(action_vec) = key.get_actions(interaction) {
-            // Execute each action of the vector
-            for action in action_vec {
-                match action {
-                    KeyAction::FeedbackPressRelease(press) => {
-                        let (layout_of_active_key, view_of_active_key) = (
-                            self.layout_of_active_key.to_string(),
-                            self.view_of_active_key.to_string(),
-                        );
-                        // Pressing a button always notifies the UI about it
-                        if *press {
-                            self.ui_connection.emit(Msg::ButtonInteraction(
-                                layout_of_active_key,
-                                view_of_active_key,
-                                key.get_id(),
-                                TapMotion::Press,
-                            ));
-                        }
-                        // A release only gets sent to the UI if the key is no longer latched
-                        else if !self.latched_keys.contains(&key.get_id()) {
-                            self.ui_connection.emit(Msg::ButtonInteraction(
-                                layout_of_active_key,
-                                view_of_active_key,
-                                key.get_id(),
-                                TapMotion::Release,
-                            ));
-                        }
-                    }
-                    KeyAction::EnterKeycode(keycode) => {
-                        let submission = Submission::Keycode(*keycode);
-                        self.submitter.submit(submission);
-                    }
-                    KeyAction::ToggleKeycode(keycode) => {
-                        let submission = Submission::ToggleKeycode(*keycode);
-                        self.submitter.submit(submission);
-                    }
-                    // Strings get decoded before they are sent
-                    KeyAction::EnterString(text) => {
-                        let decoded_submissions = self.decoder.decode_text(text.to_string());
-                        // Submit each of the returned submissions
-                        for submission in decoded_submissions {
-                            self.submitter.submit(submission);
-                        }
-                    }
-                    // Modifiers always latch. They are not released when the user lifts of the finger, but when the key is pressed a second time
-                    KeyAction::Modifier(modifier) => {
-                        let submission = Submission::Modifier(modifier.clone());
-                        let key_id = key.get_id();
-                        // If the modifier key id is present in the latched_keys HashMap, remove it
-                        if self.latched_keys.remove(&key_id) {
-                            info! {
-                                "'{}' key is no longer latched", key_id
-                            }
-                        }
-                        // Otherwise insert it
-                        else {
-                            info! {
-                                "'{}' key is now latched", key_id
-                            }
-                            self.latched_keys.insert(key_id.to_string());
-                        }
-                        self.submitter.submit(submission);
-                    }
-                    // Delete one char
-                    KeyAction::Erase => {
-                        let submission = Submission::Erase(1);
+            // The text committed for a gesture can only be erased as a whole by the action directly following the gesture
+            // Feedback actions do not count because they are also executed when the key is pressed
+            let gesture_commit = if action_vec
+                .iter()
+                .all(|action| matches!(action, KeyAction::FeedbackPressRelease(_)))
+            {
+                None
+            } else {
+                self.last_gesture_commit.take()
+            };
+            let mut erased_gesture_commit = false;
+            if let Some(gesture_commit) = gesture_commit {
+                if key.is_backspace() {
+                    if let Some(submission) = self.decoder.decode_erase_committed(&gesture_commit) {
+                        info!("Erasing the whole word that was entered with a gesture");
                         self.submitter.submit(submission);
+                        erased_gesture_commit = true;
                     }
-                    KeyAction::SwitchView(new_view) => {
-                        self.switch_layout(None, Some(new_view.to_string()), false);
+                }
+            }
+            // Execute each action of the vector
+            for action in action_vec {
+                // The BackSpace already erased the word that was entered with a gesture
+                if erased_gesture_commit
+                    && matches!(
+                        action,
+                        KeyAction::EnterKeycode(KEY_BACKSPACE) | KeyAction::Erase
+                    )
+                {
+                    continue;
+                }
+                self.execute_action(key, action);
+            }
+            // Layouts with sticky modifiers release the latched modifiers once the next key entered something
+            let entered_input = action_vec.iter().any(|action| {
+                !matches!(
+                    action,
+                    KeyAction::FeedbackPressRelease(_)
+                        | KeyAction::Modifier(_)
+                        | KeyAction::SwitchView(_)
+                        | KeyAction::TempSwitchView(_)
+                        | KeyAction::SwitchLayout(_)
+                        | KeyAction::TempSwitchLayout(_)
+                        | KeyAction::OpenPopup
+                )
+            });
+            if entered_input
+                && !key.is_modifier()
+                && self
+                    .sticky_modifier_layouts
+                    .contains(&self.layout_of_active_key)
+            {
+                self.release_latched_modifiers();
+            }
+        }
+    }
+
+    /// Execute a single action of the key
+    fn execute_action(&mut self, key: &Key, action: &KeyAction) {
+        // The composed text is committed before anything that can not be added to it. Shift keys can be used to type jamo
+        if !matches!(
+            action,
+            KeyAction::FeedbackPressRelease(_)
+                | KeyAction::EnterString(_)
+                | KeyAction::EnterKeycode(KEY_BACKSPACE)
+                | KeyAction::Erase
+                | KeyAction::SwitchView(_)
+                | KeyAction::TempSwitchView(_)
+        ) {
+            self.commit_composition();
+        }
+        match action {
+            KeyAction::FeedbackPressRelease(press) => {
+                let (layout_of_active_key, view_of_active_key) = (
+                    self.layout_of_active_key.to_string(),
+                    self.view_of_active_key.to_string(),
+                );
+                // Pressing a button always notifies the UI about it
+                if *press {
+                    self.ui_connection.emit(Msg::ButtonInteraction(
+                        layout_of_active_key,
+                        view_of_active_key,
+                        key.get_id(),
+                        TapMotion::Press,
+                    ));
+                }
+                // A release only gets sent to the UI if the key is no longer latched
+                else if !self.latched_keys.contains(&key.get_id()) {
+                    self.ui_connection.emit(Msg::ButtonInteraction(
+                        layout_of_active_key,
+                        view_of_active_key,
+                        key.get_id(),
+                        TapMotion::Release,
+                    ));
+                }
+            }
+            // BackSpace removes the jamo or letter that was typed last from the composed text
+            KeyAction::EnterKeycode(KEY_BACKSPACE) | KeyAction::Erase if self.is_composing() => {
+                if !self.hangul_composer.backspace() {
+                    if let Some(pinyin_composer) = &mut self.pinyin_composer {
+                        pinyin_composer.backspace();
                     }
-                    KeyAction::TempSwitchView(new_view) => {
-                        self.switch_layout(None, Some(new_view.to_string()), true);
+                    #[cfg(feature = "suggestions")]
+                    self.show_candidates();
+                }
+                self.show_composition();
+            }
+            KeyAction::EnterKeycode(keycode) => {
+                let submission = Submission::Keycode(*keycode);
+                self.submitter.submit(submission);
+            }
+            KeyAction::ToggleKeycode(keycode) => {
+                let submission = Submission::ToggleKeycode(*keycode);
+                self.submitter.submit(submission);
+            }
+            // Strings get decoded before they are sent
+            KeyAction::EnterString(text) => {
+                // While a command modifier is pressed, the text is part of a key combination (e.g. CTRL + 'c') and not decoded
+                if self.submitter.has_command_modifiers() {
+                    self.commit_composition();
+                    self.submitter.submit(Submission::Text(text.to_string()));
+                    // The key combination can change the text in any way
+                    self.decoder.forget_surrounding_text();
+                    return;
+                }
+                if self.compose(text) {
+                    return;
+                }
+                self.commit_composition();
+                let decoded_submissions = self.decoder.decode_text(text.to_string());
+                // Submit each of the returned submissions
+                for submission in decoded_submissions {
+                    self.submitter.submit(submission);
+                }
+            }
+            // Modifiers always latch. They are not released when the user lifts of the finger, but when the key is pressed a second time
+            KeyAction::Modifier(modifier) => {
+                let submission = Submission::Modifier(modifier.clone());
+                let key_id = key.get_id();
+                // If the modifier key id is present in the latched_keys HashMap, remove it
+                let latched = if self.latched_keys.remove(&key_id) {
+                    info! {
+                        "'{}' key is no longer latched", redact(&key_id)
                     }
-                    KeyAction::SwitchLayout(new_layout) => {
-                        self.switch_layout(Some(new_layout.to_string()), None, false);
+                    false
+                }
+                // Otherwise insert it
+                else {
+                    info! {
+                        "'{}' key is now latched", redact(&key_id)
                     }
-                    KeyAction::TempSwitchLayout(new_layout) => {
-                        self.switch_layout(Some(new_layout.to_string()), None, true);
+                    self.latched_keys.insert(key_id.to_string());
+                    true
+                };
+                self.ui_connection.emit(Msg::LatchButton(
+                    self.layout_of_active_key.to_string(),
+                    key_id,
+                    latched,
+                ));
+                self.submitter.submit(submission);
+            }
+            // Delete a part of the text around the cursor
+            KeyAction::Erase
+            | KeyAction::ErasePreviousWord
+            | KeyAction::EraseNextChar
+            | KeyAction::EraseLine => {
+                let erasure = match action {
+                    KeyAction::ErasePreviousWord => Erasure::PreviousWord,
+                    KeyAction::EraseNextChar => Erasure::NextGrapheme,
+                    KeyAction::EraseLine => Erasure::Line,
+                    _ => Erasure::PreviousGrapheme,
+                };
+                for submission in self.decoder.decode_erase(erasure) {
+                    self.submitter.submit(submission);
+                }
+            }
+            KeyAction::SwitchView(new_view) => {
+                self.switch_layout(None, Some(new_view.to_string()), false);
+            }
+            KeyAction::TempSwitchView(new_view) => {
+                self.switch_layout(None, Some(new_view.to_string()), true);
+            }
+            KeyAction::SwitchLayout(new_layout) => {
+                self.switch_layout(Some(new_layout.to_string()), None, false);
+            }
+            KeyAction::TempSwitchLayout(new_layout) => {
+                self.switch_layout(Some(new_layout.to_string()), None, true);
+            }
+            KeyAction::OpenPopup => {
+                let ui_message = Msg::OpenPopup(key.get_id());
+                self.ui_connection.emit(ui_message);
+            }
+            KeyAction::MultiTap(characters) => {
+                self.handle_multi_tap(key, characters);
+            }
+            KeyAction::Undo | KeyAction::Redo => {
+                self.undo_redo(*action == KeyAction::Undo);
+            }
+            KeyAction::Shortcut(modifiers, keycode) => {
+                let submission = Submission::Shortcut(modifiers.clone(), *keycode);
+                self.submitter.submit(submission);
+            }
+            // A new sequence replaces a sequence that is still waiting for a delay to pass. The remaining steps of the old sequence are not executed
+            KeyAction::Sequence(steps) => {
+                self.pending_sequence = None;
+                self.execute_sequence(key, steps.clone());
+            }
+        }
+    }
+
+    /// Hand the text to the input engine of the layout of the key
+    /// Returns false if the text is not composed and needs to be entered as it is
+    fn compose(&mut self, text: &str) -> bool {
+        let mut chars = text.chars();
+        let character = match (chars.next(), chars.next()) {
+            (Some(character), None) => character,
+            _ => return false,
+        };
+        match self.input_engines.get(&self.layout_of_active_key) {
+            Some(InputEngine::Hangul) if hangul::is_jamo(character) => {
+                self.compose_hangul(character);
+                true
+            }
+            Some(InputEngine::Pinyin) => self.compose_pinyin(character),
+            _ => false,
+        }
+    }
+
+    /// Add the jamo to the syllable that is composed
+    /// Syllables that are finished by it are committed and the syllable that is still composed is shown as preedit
+    fn compose_hangul(&mut self, jamo: char) {
+        let finished_text = self.hangul_composer.input(jamo);
+        if !finished_text.is_empty() {
+            self.submitter.clear_preedit();
+            for submission in self.decoder.decode_text(finished_text) {
+                self.submitter.submit(submission);
+            }
+        }
+        self.show_composition();
+    }
+
+    /// Add the letter to the pinyin and show the Hanzi it can be written with
+    /// A SPACE enters the first of them. Returns false if the character is not composed
+    fn compose_pinyin(&mut self, character: char) -> bool {
+        let pinyin_composer = match &mut self.pinyin_composer {
+            Some(pinyin_composer) => pinyin_composer,
+            None => return false,
+        };
+        if PinyinComposer::is_pinyin_letter(character) {
+            pinyin_composer.input(character);
+        } else if character == ' ' && pinyin_composer.is_composing() {
+            match pinyin_composer.choose_first() {
+                Some(candidate) => self.commit_candidate(candidate),
+                None => return false,
+            }
+            return true;
+        } else {
+            return false;
+        }
+        #[cfg(feature = "suggestions")]
+        self.show_candidates();
+        self.show_composition();
+        true
+    }
+
+    /// Commit the Hanzi that were chosen for the pinyin
+    /// Letters of the pinyin that they do not replace stay composed
+    fn commit_candidate(&mut self, candidate: String) {
+        info!("Committing the candidate {}", redact(&candidate));
+        self.submitter.clear_preedit();
+        for submission in self.decoder.decode_text(candidate) {
+            self.submitter.submit(submission);
+        }
+        #[cfg(feature = "suggestions")]
+        self.show_candidates();
+        if self.is_composing() {
+            self.show_composition();
+        }
+    }
+
+    #[cfg(feature = "suggestions")]
+    /// Show the Hanzi the pinyin can be written with in the suggestion bar
+    fn show_candidates(&self) {
+        if let Some(pinyin_composer) = &self.pinyin_composer {
+            self.ui_connection
+                .emit(Msg::Suggestions(pinyin_composer.get_candidates()));
+        }
+    }
+
+    /// Returns true while an input engine composes text
+    fn is_composing(&self) -> bool {
+        self.hangul_composer.is_composing()
+            || self
+                .pinyin_composer
+                .as_ref()
+                .map_or(false, PinyinComposer::is_composing)
+    }
+
+    /// Show the text that is composed as preedit
+    fn show_composition(&mut self) {
+        let text = match &self.pinyin_composer {
+            Some(pinyin_composer) if pinyin_composer.is_composing() => {
+                pinyin_composer.get_preedit()
+            }
+            _ => self.hangul_composer.get_preedit(),
+        };
+        let cursor = text.chars().count();
+        self.submitter.submit(Submission::Preedit { text, cursor });
+    }
+
+    /// Commit the text that is composed by the input engine
+    /// Pinyin for which no Hanzi was chosen is committed as it is
+    fn commit_composition(&mut self) {
+        if !self.is_composing() {
+            return;
+        }
+        let text = match &mut self.pinyin_composer {
+            Some(pinyin_composer) if pinyin_composer.is_composing() => pinyin_composer.commit(),
+            _ => self.hangul_composer.commit(),
+        };
+        #[cfg(feature = "suggestions")]
+        self.show_candidates();
+        info!("Committing the composed text {}", redact(&text));
+        self.submitter.clear_preedit();
+        for submission in self.decoder.decode_text(text) {
+            self.submitter.submit(submission);
+        }
+    }
+
+    /// Show the next character of the multi-tap key as preedit if the key was tapped before the timeout passed
+    /// Otherwise the previous character is committed and the first character of the key is shown
+    fn handle_multi_tap(&mut self, key: &Key, characters: &[String]) {
+        if characters.is_empty() {
+            return;
+        }
+        let timeout = Duration::from_millis(MULTI_TAP_TIMEOUT);
+        let index = match &self.multi_tap {
+            Some(multi_tap)
+                if multi_tap.key_id == key.get_id() && multi_tap.last_tap.elapsed() < timeout =>
+            {
+                (multi_tap.index + 1) % characters.len()
+            }
+            _ => {
+                self.commit_multi_tap();
+                0
+            }
+        };
+        self.multi_tap = Some(MultiTap {
+            key_id: key.get_id(),
+            characters: characters.to_vec(),
+            index,
+            last_tap: Instant::now(),
+        });
+        self.submitter.submit(Submission::Preedit {
+            text: characters[index].to_string(),
+            cursor: characters[index].chars().count(),
+        });
+        self.ui_connection
+            .emit_after(MULTI_TAP_TIMEOUT, || Msg::CheckMultiTap);
+    }
+
+    /// Commit the character of the multi-tap key if the key was not tapped again before the timeout passed
+    pub fn check_multi_tap(&mut self) {
+        let timeout = Duration::from_millis(MULTI_TAP_TIMEOUT);
+        if self
+            .multi_tap
+            .as_ref()
+            .map_or(false, |multi_tap| multi_tap.last_tap.elapsed() >= timeout)
+        {
+            self.commit_multi_tap();
+        }
+    }
+
+    /// Commit the character of the multi-tap key that is shown as preedit
+    fn commit_multi_tap(&mut self) {
+        if let Some(multi_tap) = self.multi_tap.take() {
+            let character = multi_tap.characters[multi_tap.index].to_string();
+            info!(
+                "Committing the character of the multi-tap key {}",
+                redact(&character)
+            );
+            self.submitter.clear_preedit();
+            for submission in self.decoder.decode_text(character) {
+                self.submitter.submit(submission);
+            }
+        }
+    }
+
+    /// Forget the text that is still composed because the input method was deactivated
+    /// The character of a multi-tap key and the composed syllable or pinyin are shown as preedit, which can no longer be committed
+    pub fn cancel_preedit(&mut self) {
+        self.multi_tap = None;
+        self.hangul_composer.reset();
+        if let Some(pinyin_composer) = &mut self.pinyin_composer {
+            if pinyin_composer.is_composing() {
+                pinyin_composer.reset();
+                #[cfg(feature = "suggestions")]
+                self.show_candidates();
+            }
+        }
+        self.submitter.cancel_preedit();
+    }
+
+    /// Undo the last edit or redo the last edit that was undone
+    /// The edits can only be verified if the application sends the surrounding text
+    fn undo_redo(&mut self, undo: bool) {
+        // The edits in sensitive fields are not remembered
+        if privacy::is_sensitive_field() {
+            info!("Unable to undo/redo in a sensitive field");
+            return;
+        }
+        let text_left_of_cursor =
+            if let Some(text_left_of_cursor) = self.decoder.get_current_text_left_of_cursor() {
+                text_left_of_cursor
+            } else {
+                info!("Unable to undo/redo because the surrounding text is unknown");
+                return;
+            };
+        let replaced_text = if undo {
+            self.submitter.undo(&text_left_of_cursor)
+        } else {
+            self.submitter.redo(&text_left_of_cursor)
+        };
+        if let Some((erased_text, committed_text)) = replaced_text {
+            self.decoder
+                .replace_text_left_of_cursor(&erased_text, &committed_text);
+        }
+    }
+
+    /// Forget the edits that can be undone, e.g. because a different text field was focused
+    pub fn clear_history(&mut self) {
+        self.submitter.clear_history();
+    }
+
+    /// Execute the steps of a sequence in order
+    /// If a step is a delay, the remaining steps are stored and the UI is asked to continue the sequence after the delay
+    fn execute_sequence(&mut self, key: &Key, steps: Vec<SequenceStep>) {
+        let mut steps = VecDeque::from(steps);
+        while let Some(step) = steps.pop_front() {
+            match step {
+                SequenceStep::Keycode(keycode) => {
+                    self.submitter.submit(Submission::Keycode(keycode));
+                }
+                SequenceStep::Delay { delay } => {
+                    if !steps.is_empty() {
+                        self.no_sequence_delays = self.no_sequence_delays.wrapping_add(1);
+                        let delay_number = self.no_sequence_delays;
+                        self.pending_sequence = Some((delay_number, key.clone(), steps.into()));
+                        self.ui_connection
+                            .emit_after(delay, move || Msg::ContinueSequence(delay_number));
                     }
-                    KeyAction::OpenPopup => {
-                        let ui_message = Msg::OpenPopup(key.get_id());
-                        self.ui_connection.emit(ui_message);
+                    return;
+                }
+                // The steps of a nested sequence are executed in place
+                SequenceStep::Action(KeyAction::Sequence(nested_steps)) => {
+                    for nested_step in nested_steps.into_iter().rev() {
+                        steps.push_front(nested_step);
                     }
                 }
+                SequenceStep::Action(action) => {
+                    self.execute_action(key, &action);
+                }
             }
         }
     }
 
+    /// Execute the remaining steps of the sequence that waited for the delay with the number to pass
+    /// If the sequence was replaced or cancelled in the meantime, nothing happens
+    pub fn continue_sequence(&mut self, delay_number: usize) {
+        if self
+            .pending_sequence
+            .as_ref()
+            .map_or(true, |(pending_delay_number, _, _)| {
+                *pending_delay_number != delay_number
+            })
+        {
+            return;
+        }
+        if let Some((_, key, steps)) = self.pending_sequence.take() {
+            info!(
+                "Keyboard continues the sequence of key {}",
+                redact(key.get_id())
+            );
+            self.execute_sequence(&key, steps);
+        }
+    }
+
     /// Tells the UI to switch to a different layout/view and if it is not a permanent switch, it stores the layout/view to switch back to when the next button is pressed
     fn switch_layout(
         &mut self,
@@ -414,13 +1589,42 @@ impl Keyboard {
     /// Fetch the events from the wayland event queue
     pub fn fetch_events(&mut self) {
         self.submitter.fetch_events();
+        // The surrounding text might show that the next letter needs to be capitalized
+        if self.decoder.surrounding_text_changed() {
+            let text_left_of_cursor = self.decoder.get_text_left_of_cursor().to_string();
+            self.update_auto_capitalization(&text_left_of_cursor);
+        }
     }
 
     /// Submit the text
     pub fn submit_text(&mut self, text: String, append_space: bool) {
+        // A chosen candidate replaces the pinyin it was suggested for
+        if let Some(candidate) = self
+            .pinyin_composer
+            .as_mut()
+            .and_then(|pinyin_composer| pinyin_composer.choose(&text))
+        {
+            self.commit_candidate(candidate);
+            return;
+        }
+        self.commit_multi_tap();
+        self.commit_composition();
+        // The chosen suggestion might be a snippet
+        if append_space {
+            if let Some(decoded_submissions) = self.decoder.decode_suggestion(&text) {
+                for submission in decoded_submissions {
+                    self.submitter.submit(submission);
+                }
+                return;
+            }
+            // A chosen completion replaces the beginning of the word
+            if let Some(erasure) = self.decoder.decode_completion(&text) {
+                self.submitter.submit(erasure);
+            }
+        }
         self.submitter.submit(Submission::Text(text));
         if append_space {
-            let decoded_submissions = self.decoder.decode_text(" ".to_string());
+            let decoded_submissions = self.decoder.decode_automatic_space();
             // Submit each of the returned submissions
             for submission in decoded_submissions {
                 self.submitter.submit(submission);
@@ -428,3 +1632,29 @@ impl Keyboard {
         }
     }
 }
+
+/// Returns true if the text ends at the start of a sentence
+/// This is the case at the start of the text, at the start of a line and after a '.', '!' or '?' that is followed by whitespace
+fn is_sentence_start(text: &str) -> bool {
+    let text_before_whitespace = text.trim_end_matches(char::is_whitespace);
+    let trailing_whitespace = &text[text_before_whitespace.len()..];
+    text_before_whitespace.is_empty()
+        || trailing_whitespace.contains('\n')
+        || (!trailing_whitespace.is_empty() && text_before_whitespace.ends_with(['.', '!', '?']))
+}
+
+/// Get the direction of a flick from how far the finger moved
+/// The y coordinate grows downwards
+fn get_flick_direction(dx: f64, dy: f64) -> FlickDirection {
+    if dx.abs() > dy.abs() {
+        if dx > 0.0 {
+            FlickDirection::Right
+        } else {
+            FlickDirection::Left
+        }
+    } else if dy > 0.0 {
+        FlickDirection::Down
+    } else {
+        FlickDirection::Up
+    }
+}
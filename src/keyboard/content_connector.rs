@@ -2,13 +2,14 @@
 use std::sync::mpsc;
 use zwp_input_method_service::ReceiveSurroundingText;
 
+/// The surrounding text is sent to the decoder. 'None' is sent by the UIConnector when the focus changes and the surrounding text is unknown
 pub struct ContentConnector {
-    pub transmitter: mpsc::Sender<(String, String)>,
+    pub transmitter: mpsc::Sender<Option<(String, String)>>,
 }
 
 impl ContentConnector {
     /// Creates a new ContentConnector
-    pub fn new(transmitter: mpsc::Sender<(String, String)>) -> ContentConnector {
+    pub fn new(transmitter: mpsc::Sender<Option<(String, String)>>) -> ContentConnector {
         ContentConnector { transmitter }
     }
 }
@@ -17,7 +18,7 @@ impl ContentConnector {
 impl ReceiveSurroundingText for ContentConnector {
     fn text_changed(&self, string_left_of_cursor: String, string_right_of_cursor: String) {
         self.transmitter
-            .send((string_left_of_cursor, string_right_of_cursor))
+            .send(Some((string_left_of_cursor, string_right_of_cursor)))
             .unwrap();
     }
 }
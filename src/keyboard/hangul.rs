@@ -0,0 +1,371 @@
+/// The consonants that can start a syllable in the order of the Unicode syllable blocks
+const INITIALS: [char; 19] = [
+    'ㄱ', 'ㄲ', 'ㄴ', 'ㄷ', 'ㄸ', 'ㄹ', 'ㅁ', 'ㅂ', 'ㅃ', 'ㅅ', 'ㅆ', 'ㅇ', 'ㅈ', 'ㅉ', 'ㅊ', 'ㅋ',
+    'ㅌ', 'ㅍ', 'ㅎ',
+];
+/// The vowels in the order of the Unicode syllable blocks. They are also in this order in the compatibility jamo block
+const VOWELS: [char; 21] = [
+    'ㅏ', 'ㅐ', 'ㅑ', 'ㅒ', 'ㅓ', 'ㅔ', 'ㅕ', 'ㅖ', 'ㅗ', 'ㅘ', 'ㅙ', 'ㅚ', 'ㅛ', 'ㅜ', 'ㅝ', 'ㅞ',
+    'ㅟ', 'ㅠ', 'ㅡ', 'ㅢ', 'ㅣ',
+];
+/// The consonants that can end a syllable in the order of the Unicode syllable blocks. A syllable without one has the index 0
+const FINALS: [char; 27] = [
+    'ㄱ', 'ㄲ', 'ㄳ', 'ㄴ', 'ㄵ', 'ㄶ', 'ㄷ', 'ㄹ', 'ㄺ', 'ㄻ', 'ㄼ', 'ㄽ', 'ㄾ', 'ㄿ', 'ㅀ', 'ㅁ',
+    'ㅂ', 'ㅄ', 'ㅅ', 'ㅆ', 'ㅇ', 'ㅈ', 'ㅊ', 'ㅋ', 'ㅌ', 'ㅍ', 'ㅎ',
+];
+/// Vowels that are typed as two keystrokes and the vowel they combine to
+const COMPOUND_VOWELS: [(char, char, char); 7] = [
+    ('ㅗ', 'ㅏ', 'ㅘ'),
+    ('ㅗ', 'ㅐ', 'ㅙ'),
+    ('ㅗ', 'ㅣ', 'ㅚ'),
+    ('ㅜ', 'ㅓ', 'ㅝ'),
+    ('ㅜ', 'ㅔ', 'ㅞ'),
+    ('ㅜ', 'ㅣ', 'ㅟ'),
+    ('ㅡ', 'ㅣ', 'ㅢ'),
+];
+/// Final consonants that are typed as two keystrokes and the final consonant they combine to
+const COMPOUND_FINALS: [(char, char, char); 11] = [
+    ('ㄱ', 'ㅅ', 'ㄳ'),
+    ('ㄴ', 'ㅈ', 'ㄵ'),
+    ('ㄴ', 'ㅎ', 'ㄶ'),
+    ('ㄹ', 'ㄱ', 'ㄺ'),
+    ('ㄹ', 'ㅁ', 'ㄻ'),
+    ('ㄹ', 'ㅂ', 'ㄼ'),
+    ('ㄹ', 'ㅅ', 'ㄽ'),
+    ('ㄹ', 'ㅌ', 'ㄾ'),
+    ('ㄹ', 'ㅍ', 'ㄿ'),
+    ('ㄹ', 'ㅎ', 'ㅀ'),
+    ('ㅂ', 'ㅅ', 'ㅄ'),
+];
+/// First of the precomposed syllable blocks ('가')
+const FIRST_SYLLABLE: u32 = 0xAC00;
+
+/// Returns true if the character is one of the jamo of the Hangul Compatibility Jamo block that are typed on 2-set layouts
+pub fn is_jamo(character: char) -> bool {
+    ('ㄱ'..='ㅣ').contains(&character)
+}
+
+/// Returns true if the jamo is a vowel
+fn is_vowel(jamo: char) -> bool {
+    VOWELS.contains(&jamo)
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+/// The jamo of a syllable block that is composed
+struct Syllable {
+    initial: Option<char>,
+    vowel: Option<char>,
+    final_consonant: Option<char>,
+}
+
+impl Syllable {
+    /// Returns the syllable block or the single jamo if it is not a complete syllable
+    fn to_text(self) -> String {
+        match (self.initial, self.vowel) {
+            (Some(initial), Some(vowel)) => {
+                let initial_index = INITIALS.iter().position(|&jamo| jamo == initial);
+                let vowel_index = VOWELS.iter().position(|&jamo| jamo == vowel);
+                let final_index = match self.final_consonant {
+                    Some(final_consonant) => FINALS
+                        .iter()
+                        .position(|&jamo| jamo == final_consonant)
+                        .map(|index| index + 1),
+                    None => Some(0),
+                };
+                match (initial_index, vowel_index, final_index) {
+                    (Some(initial_index), Some(vowel_index), Some(final_index)) => {
+                        let code_point = FIRST_SYLLABLE
+                            + ((initial_index * VOWELS.len() + vowel_index) * (FINALS.len() + 1)
+                                + final_index) as u32;
+                        char::from_u32(code_point).unwrap().to_string()
+                    }
+                    _ => String::new(),
+                }
+            }
+            (Some(jamo), None) | (None, Some(jamo)) => jamo.to_string(),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+/// The HangulComposer combines the jamo typed on a 2-set (Dubeolsik) layout to syllable blocks
+///
+/// Only the syllable that is typed is composed. It is finished when the next jamo can not be added to it.
+/// A vowel that follows a final consonant takes it (or the second part of a compound final consonant) as its initial consonant.
+/// The keystrokes of the syllable are remembered, so BackSpace removes the jamo in the order they were typed
+pub struct HangulComposer {
+    syllable: Syllable,
+    keystrokes: Vec<char>, // Jamo that were typed for the syllable
+}
+
+impl HangulComposer {
+    /// Creates a new HangulComposer that does not compose a syllable
+    pub fn new() -> HangulComposer {
+        HangulComposer {
+            syllable: Syllable::default(),
+            keystrokes: Vec::new(),
+        }
+    }
+
+    /// Returns true while a syllable is composed
+    pub fn is_composing(&self) -> bool {
+        !self.keystrokes.is_empty()
+    }
+
+    /// Returns the syllable that is composed, so it can be shown as preedit
+    pub fn get_preedit(&self) -> String {
+        self.syllable.to_text()
+    }
+
+    /// Adds the typed character to the syllable
+    /// Returns the text of the syllables that were finished by it. Characters that are not jamo finish the syllable and are returned after it
+    pub fn input(&mut self, character: char) -> String {
+        if !is_jamo(character) {
+            let mut text = self.commit();
+            text.push(character);
+            return text;
+        }
+        let Syllable {
+            initial,
+            vowel,
+            final_consonant,
+        } = self.syllable;
+        if is_vowel(character) {
+            match (vowel, final_consonant) {
+                (None, _) => {
+                    self.syllable.vowel = Some(character);
+                }
+                (Some(vowel), None) => match combine(&COMPOUND_VOWELS, vowel, character) {
+                    Some(compound_vowel) => self.syllable.vowel = Some(compound_vowel),
+                    None => return self.start_syllable(None, character),
+                },
+                // The final consonant becomes the initial consonant of the next syllable
+                (Some(_), Some(final_consonant)) => {
+                    let (kept_consonant, moved_consonant) = split(final_consonant);
+                    self.syllable.final_consonant = kept_consonant;
+                    return self.start_syllable(Some(moved_consonant), character);
+                }
+            }
+        } else {
+            match (initial, vowel, final_consonant) {
+                (None, None, _) if INITIALS.contains(&character) => {
+                    self.syllable.initial = Some(character);
+                }
+                (Some(_), Some(_), None) if FINALS.contains(&character) => {
+                    self.syllable.final_consonant = Some(character);
+                }
+                (Some(_), Some(_), Some(final_consonant)) => {
+                    match combine(&COMPOUND_FINALS, final_consonant, character) {
+                        Some(compound_final) => {
+                            self.syllable.final_consonant = Some(compound_final)
+                        }
+                        None => return self.start_syllable(None, character),
+                    }
+                }
+                _ => return self.start_syllable(None, character),
+            }
+        }
+        self.keystrokes.push(character);
+        String::new()
+    }
+
+    /// Removes the jamo that was typed last from the syllable
+    /// Returns false if no syllable was composed
+    pub fn backspace(&mut self) -> bool {
+        if self.keystrokes.pop().is_none() {
+            return false;
+        }
+        // The syllable is composed again without the jamo. None of its keystrokes finish it
+        let keystrokes = std::mem::take(&mut self.keystrokes);
+        self.syllable = Syllable::default();
+        for jamo in keystrokes {
+            self.input(jamo);
+        }
+        true
+    }
+
+    /// Finishes the syllable and returns its text
+    pub fn commit(&mut self) -> String {
+        let text = self.syllable.to_text();
+        self.reset();
+        text
+    }
+
+    /// Forgets the syllable without returning it, e.g. because its preedit was already committed
+    pub fn reset(&mut self) {
+        self.syllable = Syllable::default();
+        self.keystrokes.clear();
+    }
+
+    /// Finishes the syllable and starts a new one with the jamo
+    /// Returns the text of the finished syllable. A consonant that can not start a syllable is returned with it
+    fn start_syllable(&mut self, initial: Option<char>, jamo: char) -> String {
+        let mut text = self.commit();
+        if let Some(initial) = initial {
+            self.syllable.initial = Some(initial);
+            self.keystrokes.push(initial);
+        }
+        if is_vowel(jamo) {
+            self.syllable.vowel = Some(jamo);
+        } else if INITIALS.contains(&jamo) {
+            self.syllable.initial = Some(jamo);
+        } else {
+            text.push(jamo);
+            return text;
+        }
+        self.keystrokes.push(jamo);
+        text
+    }
+}
+
+/// Returns the jamo the two jamo combine to if they are listed in the table
+fn combine(table: &[(char, char, char)], first: char, second: char) -> Option<char> {
+    table
+        .iter()
+        .find(|(first_part, second_part, _)| *first_part == first && *second_part == second)
+        .map(|(_, _, compound)| *compound)
+}
+
+/// Splits a final consonant that is followed by a vowel
+/// Returns the final consonant that stays in the syllable and the consonant that is moved to the next syllable
+fn split(final_consonant: char) -> (Option<char>, char) {
+    match COMPOUND_FINALS
+        .iter()
+        .find(|(_, _, compound)| *compound == final_consonant)
+    {
+        Some((first_part, second_part, _)) => (Some(*first_part), *second_part),
+        None => (None, final_consonant),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Types the jamo and returns the committed text and the preedit
+    fn type_jamo(composer: &mut HangulComposer, jamo: &str) -> (String, String) {
+        let committed: String = jamo.chars().map(|jamo| composer.input(jamo)).collect();
+        (committed, composer.get_preedit())
+    }
+
+    #[test]
+    fn composes_syllables() {
+        let mut composer = HangulComposer::new();
+        assert_eq!(type_jamo(&mut composer, "ㄱ"), ("".into(), "ㄱ".into()));
+        assert_eq!(type_jamo(&mut composer, "ㅏ"), ("".into(), "가".into()));
+        assert_eq!(type_jamo(&mut composer, "ㄱ"), ("".into(), "각".into()));
+        assert_eq!(composer.commit(), "각");
+        assert!(!composer.is_composing());
+        assert_eq!(type_jamo(&mut composer, "ㅎㅏㄴ"), ("".into(), "한".into()));
+    }
+
+    #[test]
+    fn moves_final_consonant_to_next_syllable() {
+        let mut composer = HangulComposer::new();
+        assert_eq!(
+            type_jamo(&mut composer, "ㅎㅏㄴㄱㅡㄹ"),
+            ("한".into(), "글".into())
+        );
+        composer.reset();
+        assert_eq!(
+            type_jamo(&mut composer, "ㄱㅏㄱㅏ"),
+            ("가".into(), "가".into())
+        );
+    }
+
+    #[test]
+    fn splits_compound_final_consonant() {
+        let mut composer = HangulComposer::new();
+        assert_eq!(
+            type_jamo(&mut composer, "ㄷㅏㄹㄱ"),
+            ("".into(), "닭".into())
+        );
+        assert_eq!(type_jamo(&mut composer, "ㅏ"), ("달".into(), "가".into()));
+        composer.reset();
+        assert_eq!(
+            type_jamo(&mut composer, "ㅇㅓㅂㅅ"),
+            ("".into(), "없".into())
+        );
+        assert_eq!(type_jamo(&mut composer, "ㅇ"), ("없".into(), "ㅇ".into()));
+        composer.reset();
+        assert_eq!(
+            type_jamo(&mut composer, "ㅇㅓㅂㅅㅓ"),
+            ("업".into(), "서".into())
+        );
+    }
+
+    #[test]
+    fn combines_compound_vowels() {
+        let mut composer = HangulComposer::new();
+        assert_eq!(type_jamo(&mut composer, "ㅇㅗㅏ"), ("".into(), "와".into()));
+        assert_eq!(type_jamo(&mut composer, "ㄴ"), ("".into(), "완".into()));
+        composer.reset();
+        assert_eq!(type_jamo(&mut composer, "ㅇㅡㅣ"), ("".into(), "의".into()));
+        composer.reset();
+        assert_eq!(type_jamo(&mut composer, "ㄱㅜㅓ"), ("".into(), "궈".into()));
+        assert_eq!(type_jamo(&mut composer, "ㅏ"), ("궈".into(), "ㅏ".into()));
+    }
+
+    #[test]
+    fn finishes_syllable_with_jamo_that_do_not_fit() {
+        let mut composer = HangulComposer::new();
+        // Two consonants without a vowel
+        assert_eq!(type_jamo(&mut composer, "ㄱㄴ"), ("ㄱ".into(), "ㄴ".into()));
+        composer.reset();
+        // Two vowels that do not combine
+        assert_eq!(type_jamo(&mut composer, "ㅏㅏ"), ("ㅏ".into(), "ㅏ".into()));
+        composer.reset();
+        // Double consonants that can only start a syllable
+        assert_eq!(
+            type_jamo(&mut composer, "ㄱㅏㄸ"),
+            ("가".into(), "ㄸ".into())
+        );
+        composer.reset();
+        // Final consonants that do not combine
+        assert_eq!(
+            type_jamo(&mut composer, "ㄱㅏㄴㄱ"),
+            ("간".into(), "ㄱ".into())
+        );
+        composer.reset();
+        // Compound consonants that can only end a syllable
+        assert_eq!(type_jamo(&mut composer, "ㄱㅏㄳ"), ("".into(), "갃".into()));
+        assert_eq!(type_jamo(&mut composer, "ㄳ"), ("갃ㄳ".into(), "".into()));
+        assert!(!composer.is_composing());
+    }
+
+    #[test]
+    fn finishes_syllable_with_other_characters() {
+        let mut composer = HangulComposer::new();
+        assert_eq!(type_jamo(&mut composer, "ㄱㅏ."), ("가.".into(), "".into()));
+        assert!(!composer.is_composing());
+        assert_eq!(composer.input(' '), " ");
+    }
+
+    #[test]
+    fn backspace_removes_single_jamo() {
+        let mut composer = HangulComposer::new();
+        type_jamo(&mut composer, "ㄷㅏㄹㄱ");
+        let mut preedits = Vec::new();
+        while composer.backspace() {
+            preedits.push(composer.get_preedit());
+        }
+        assert_eq!(preedits, vec!["달", "다", "ㄷ", ""]);
+        assert!(!composer.is_composing());
+
+        type_jamo(&mut composer, "ㄱㅗㅏ");
+        assert!(composer.backspace());
+        assert_eq!(composer.get_preedit(), "고");
+    }
+
+    #[test]
+    fn backspace_after_moved_final_consonant() {
+        let mut composer = HangulComposer::new();
+        assert_eq!(
+            type_jamo(&mut composer, "ㄱㅏㄱㅏ"),
+            ("가".into(), "가".into())
+        );
+        assert!(composer.backspace());
+        assert_eq!(composer.get_preedit(), "ㄱ");
+        assert_eq!(type_jamo(&mut composer, "ㅗ"), ("".into(), "고".into()));
+    }
+}
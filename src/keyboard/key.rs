@@ -3,12 +3,15 @@ use std::collections::HashMap;
 
 // Imports from other modules
 use crate::keyboard::{Interaction, KeyAction, KeyMeta, TapDuration, TapMotion};
+use crate::submitter::{KEY_BACKSPACE, KEY_SPACE};
 
 #[derive(Debug, Clone)]
 /// A key stores all actions that will be executed when the key is pressed as well as its id
 pub struct Key {
     id: String,
     actions: HashMap<Interaction, Vec<KeyAction>>,
+    long_press_delay: Option<u64>,
+    repeat: bool,
 }
 
 impl Key {
@@ -19,9 +22,21 @@ impl Key {
         Key {
             id: key_name.to_string(),
             actions,
+            long_press_delay: key_meta.long_press_delay,
+            repeat: key_meta.repeat,
         }
     }
 
+    /// Returns the milliseconds the key needs to be held for a long press, if the key or its layout defines them
+    pub fn get_long_press_delay(&self) -> Option<u64> {
+        self.long_press_delay
+    }
+
+    /// Returns true if the short press of the key is repeated while the key is held down
+    pub fn repeats(&self) -> bool {
+        self.repeat
+    }
+
     /// Returns the id of the Key
     pub fn get_id(&self) -> String {
         self.id.clone()
@@ -32,6 +47,64 @@ impl Key {
         self.actions.get(&interaction)
     }
 
+    /// Returns the keycodes a tap of the key enters
+    pub fn get_keycodes(&self) -> Vec<u32> {
+        self.get_actions(Interaction::Tap(TapDuration::Short, TapMotion::Release))
+            .map_or(Vec::new(), |actions| {
+                actions
+                    .iter()
+                    .filter_map(|action| match action {
+                        KeyAction::EnterKeycode(keycode) | KeyAction::ToggleKeycode(keycode) => {
+                            Some(*keycode)
+                        }
+                        _ => None,
+                    })
+                    .collect()
+            })
+    }
+
+    /// Returns true if the key has actions for at least one flick direction
+    pub fn has_flick_actions(&self) -> bool {
+        self.actions
+            .keys()
+            .any(|interaction| matches!(interaction, Interaction::Flick(_)))
+    }
+
+    /// Returns true if tapping the key erases the char before the cursor
+    pub fn is_backspace(&self) -> bool {
+        self.get_actions(Interaction::Tap(TapDuration::Short, TapMotion::Release))
+            .map_or(false, |actions| {
+                actions.iter().any(|action| {
+                    matches!(
+                        action,
+                        KeyAction::EnterKeycode(KEY_BACKSPACE) | KeyAction::Erase
+                    )
+                })
+            })
+    }
+
+    /// Returns true if pressing the key latches a modifier
+    pub fn is_modifier(&self) -> bool {
+        self.get_actions(Interaction::Tap(TapDuration::Short, TapMotion::Press))
+            .map_or(false, |actions| {
+                actions
+                    .iter()
+                    .any(|action| matches!(action, KeyAction::Modifier(_)))
+            })
+    }
+
+    /// Returns true if tapping the key enters a space
+    pub fn is_space(&self) -> bool {
+        self.get_actions(Interaction::Tap(TapDuration::Short, TapMotion::Release))
+            .map_or(false, |actions| {
+                actions.iter().any(|action| match action {
+                    KeyAction::EnterKeycode(keycode) => *keycode == KEY_SPACE,
+                    KeyAction::EnterString(text) => text == " ",
+                    _ => false,
+                })
+            })
+    }
+
     /// Add the action to give feedback when a button is pressed or released to all vectors of KeyActions
     fn add_feedback_actions(actions: &mut HashMap<Interaction, Vec<KeyAction>>) {
         // Create a vector of all variants of TapDuration and TapMotion to get all combinations in the following for loop
@@ -40,6 +113,11 @@ impl Key {
         tap_motions.push(TapMotion::Press);
         tap_motions.push(TapMotion::Release);
 
+        // A double tap releases the button
+        if let Some(actions_vec) = actions.get_mut(&Interaction::DoubleTap) {
+            actions_vec.push(KeyAction::FeedbackPressRelease(false));
+        }
+
         // Add the action for each of the combinations
         for duration in durations {
             for tap_motion in &tap_motions {
@@ -2,16 +2,20 @@
 use std::collections::HashMap;
 
 // Imports from other modules
-use crate::keyboard::{Interaction, TapDuration, TapMotion};
+use crate::keyboard::{FlickDirection, Interaction, TapDuration, TapMotion};
 
 // Modules
+mod accents;
 mod deserialized_structs;
 mod deserializer;
-use deserialized_structs::{KeyDeserialized, KeyIds, LayoutDeserialized};
+use accents::AccentTable;
+use deserialized_structs::{KeyDeserialized, KeyIds, LayoutDeserialized, LetterLongPress};
 use deserializer::LayoutYamlParser;
 
 // Re-exports
-pub use deserialized_structs::{KeyAction, KeyDisplay, KeyEvent, Modifier, Outline};
+pub use deserialized_structs::{
+    InputEngine, KeyAction, KeyDisplay, KeyEvent, Modifier, Outline, SequenceStep,
+};
 
 #[derive(Debug)]
 /// Struct to save all information needed to build a key and its representation as a button
@@ -22,11 +26,26 @@ pub struct KeyMeta {
     pub outline: Outline,
     pub popup: Option<Vec<String>>,
     pub styles: Option<Vec<String>>,
+    pub hint: Option<String>,
+    pub long_press_delay: Option<u64>,
+    pub repeat: bool,
+}
+
+/// Settings of a layout that are needed to build the KeyMeta of its keys
+struct KeyDefaults<'a> {
+    letter_long_press: LetterLongPress,
+    accent_table: Option<&'a AccentTable>,
+    show_hints: bool,
+    long_press_delay: Option<u64>,
 }
 
 impl KeyMeta {
     /// Convert the KeyDeserialized struct to a KeyMeta struct. This is done by copying the information that was deserialized and supplementing it with default values
-    fn from(key_id: &str, key_deserialized: Option<&KeyDeserialized>) -> KeyMeta {
+    fn from(
+        key_id: &str,
+        key_deserialized: Option<&KeyDeserialized>,
+        key_defaults: &KeyDefaults,
+    ) -> KeyMeta {
         // Make a default KeyMeta struct
         let mut key_meta = KeyMeta::default(key_id);
         // If some of the information about the key was provided in the layout definition file, overwrite the default values
@@ -49,6 +68,57 @@ impl KeyMeta {
                 key_meta.styles = key_deserialized.styles.clone();
             };
         }
+        // Keys without their own long press delay use the delay of the layout
+        key_meta.long_press_delay = key_deserialized
+            .and_then(|key_deserialized| key_deserialized.long_press_delay)
+            .or(key_defaults.long_press_delay);
+        key_meta.repeat = key_deserialized
+            .and_then(|key_deserialized| key_deserialized.repeat)
+            .unwrap_or(false);
+        // Letters get a popup with their variants from the accent table, unless the layout defines their popup
+        if key_meta.popup.is_none() {
+            key_meta.popup = key_defaults
+                .accent_table
+                .and_then(|accent_table| accent_table.get_popup(key_id));
+        }
+        // Letters can open their popup with a long press instead of entering the uppercase letter, if the layout does not define their long press
+        let long_press_defined = key_deserialized
+            .and_then(|key_deserialized| key_deserialized.actions.as_ref())
+            .map_or(false, |actions| actions.contains_key(&KeyEvent::LongPress));
+        let is_letter = key_id.chars().count() == 1 && key_id.chars().all(char::is_alphabetic);
+        if key_defaults.letter_long_press == LetterLongPress::Popup
+            && is_letter
+            && key_meta.popup.is_some()
+            && !long_press_defined
+        {
+            key_meta
+                .actions
+                .remove(&Interaction::Tap(TapDuration::Long, TapMotion::Release));
+            key_meta.actions.insert(
+                Interaction::Tap(TapDuration::Long, TapMotion::Press),
+                vec![KeyAction::OpenPopup],
+            );
+        }
+        // The hint shows what a long press or the popup of the key enters
+        key_meta.hint = key_deserialized.and_then(|key_deserialized| key_deserialized.hint.clone());
+        if key_meta.hint.is_none() && key_defaults.show_hints {
+            let long_press_string = key_deserialized
+                .and_then(|key_deserialized| key_deserialized.actions.as_ref())
+                .and_then(|actions| actions.get(&KeyEvent::LongPress))
+                .and_then(|actions| {
+                    actions.iter().find_map(|action| match action {
+                        KeyAction::EnterString(text) => Some(text.clone()),
+                        _ => None,
+                    })
+                });
+            let first_popup_entry = key_meta
+                .popup
+                .as_ref()
+                .and_then(|popup| popup.first())
+                .and_then(|popup_row| popup_row.split_whitespace().next())
+                .map(str::to_string);
+            key_meta.hint = long_press_string.or(first_popup_entry);
+        }
         key_meta
     }
 
@@ -78,6 +148,12 @@ impl KeyMeta {
         let popup = None;
         // No css style classes are added
         let styles = None;
+        // No hint is shown
+        let hint = None;
+        // The default long press delay is used
+        let long_press_delay = None;
+        // The key does not repeat when it is held
+        let repeat = false;
 
         KeyMeta {
             actions,
@@ -85,6 +161,9 @@ impl KeyMeta {
             outline,
             popup,
             styles,
+            hint,
+            long_press_delay,
+            repeat,
         }
     }
 
@@ -100,10 +179,38 @@ impl KeyMeta {
             // All actions are executed on release
             // Except for the toggle_keycode with a long press, then one action is created for the long press and one for its release
             // And except for the Modifier action. This action gets activated with the press
+            // A flick is not pressed and released. All of its actions are executed when the flick is finished
+            // The actions of a double tap are executed when the key is released the second time
+            if *key_event == KeyEvent::DoubleTap {
+                let double_tap_actions = key_action_vec
+                    .iter()
+                    .filter(|action| !matches!(action, KeyAction::FeedbackPressRelease(_)))
+                    .cloned()
+                    .collect();
+                actions.insert(Interaction::DoubleTap, double_tap_actions);
+                continue;
+            }
+            let flick_direction = match key_event {
+                KeyEvent::ShortPress | KeyEvent::LongPress | KeyEvent::DoubleTap => None,
+                KeyEvent::SwipeUp => Some(FlickDirection::Up),
+                KeyEvent::SwipeDown => Some(FlickDirection::Down),
+                KeyEvent::SwipeLeft => Some(FlickDirection::Left),
+                KeyEvent::SwipeRight => Some(FlickDirection::Right),
+            };
+            if let Some(flick_direction) = flick_direction {
+                let flick_actions = key_action_vec
+                    .iter()
+                    .filter(|action| !matches!(action, KeyAction::FeedbackPressRelease(_)))
+                    .cloned()
+                    .collect();
+                actions.insert(Interaction::Flick(flick_direction), flick_actions);
+                continue;
+            }
             // Get the duration of the tap
-            let tap_duration = match key_event {
-                KeyEvent::ShortPress => TapDuration::Short,
-                KeyEvent::LongPress => TapDuration::Long,
+            let tap_duration = if *key_event == KeyEvent::LongPress {
+                TapDuration::Long
+            } else {
+                TapDuration::Short
             };
             for action in key_action_vec {
                 let mut activate_when_pressed = false;
@@ -120,6 +227,10 @@ impl KeyMeta {
                     KeyAction::Modifier(_) => {
                         activate_when_pressed = true;
                     }
+                    // A long press opens the popup while the finger is still down, so an entry can be selected by sliding the finger to it
+                    KeyAction::OpenPopup if tap_duration == TapDuration::Long => {
+                        activate_when_pressed = true;
+                    }
 
                     // All other key actions are executed when the key is released
                     KeyAction::EnterKeycode(_)
@@ -129,7 +240,15 @@ impl KeyMeta {
                     | KeyAction::SwitchLayout(_)
                     | KeyAction::TempSwitchLayout(_)
                     | KeyAction::Erase
-                    | KeyAction::OpenPopup => {
+                    | KeyAction::ErasePreviousWord
+                    | KeyAction::EraseNextChar
+                    | KeyAction::EraseLine
+                    | KeyAction::OpenPopup
+                    | KeyAction::MultiTap(_)
+                    | KeyAction::Undo
+                    | KeyAction::Redo
+                    | KeyAction::Shortcut(_, _)
+                    | KeyAction::Sequence(_) => {
                         activate_when_released = true;
                     }
                 }
@@ -180,6 +299,11 @@ impl KeyMeta {
 pub struct LayoutMeta {
     pub views: HashMap<String, KeyArrangement>,
     pub keys: HashMap<String, KeyMeta>,
+    pub key_preview: bool,
+    pub direct: bool,
+    pub shifted_view: Option<String>,
+    pub sticky_modifiers: bool,
+    pub input_engine: Option<InputEngine>,
 }
 
 impl LayoutMeta {
@@ -200,11 +324,24 @@ impl LayoutMeta {
     fn from(layout_deserialized: LayoutDeserialized) -> LayoutMeta {
         let mut views = HashMap::new();
         let mut keys = HashMap::new();
+        let accent_table = layout_deserialized
+            .accents
+            .as_deref()
+            .and_then(AccentTable::load);
+        let key_defaults = KeyDefaults {
+            letter_long_press: layout_deserialized.letter_long_press,
+            accent_table: accent_table.as_ref(),
+            show_hints: layout_deserialized.show_hints,
+            long_press_delay: layout_deserialized.long_press_delay,
+        };
         // For each view..
         for (view_name, key_arrangement) in layout_deserialized.views {
             // The KeyMeta for all its keys is created and added to the HashMap
-            let keys_for_view =
-                LayoutMeta::get_key_meta_for_all_keys(&key_arrangement, &layout_deserialized.keys);
+            let keys_for_view = LayoutMeta::get_key_meta_for_all_keys(
+                &key_arrangement,
+                &layout_deserialized.keys,
+                &key_defaults,
+            );
             keys.extend(keys_for_view);
             //for (key_id, key_meta) in keys_for_view {
             //keys.insert(key_id, key_meta); // Could use map1.extend(map2); instead
@@ -212,7 +349,15 @@ impl LayoutMeta {
             let view = KeyArrangement::from(&key_arrangement, &keys);
             views.insert(view_name, view);
         }
-        LayoutMeta { views, keys }
+        LayoutMeta {
+            views,
+            keys,
+            key_preview: layout_deserialized.key_preview,
+            direct: layout_deserialized.direct,
+            shifted_view: layout_deserialized.shifted_view,
+            sticky_modifiers: layout_deserialized.sticky_modifiers,
+            input_engine: layout_deserialized.input_engine,
+        }
     }
 
     /// Gets the KeyMeta for all keys
@@ -220,13 +365,14 @@ impl LayoutMeta {
     fn get_key_meta_for_all_keys(
         key_arrangement_deserialized: &[KeyIds],
         key_meta: &HashMap<String, KeyDeserialized>,
+        key_defaults: &KeyDefaults,
     ) -> HashMap<String, KeyMeta> {
         let mut keys = HashMap::new();
         // For all keys..
         for row in key_arrangement_deserialized {
             for key_id in row.split_whitespace() {
                 // create the KeyMeta for that key_id and add it to the HashMap
-                let key_meta = KeyMeta::from(key_id, key_meta.get(key_id));
+                let key_meta = KeyMeta::from(key_id, key_meta.get(key_id), key_defaults);
                 keys.insert(key_id.to_string(), key_meta);
             }
         }
@@ -0,0 +1,74 @@
+// Imports from other crates
+use std::collections::HashMap;
+use std::fs;
+
+// Imports from other modules
+use crate::config::directories;
+
+/// The AccentTable maps base letters to the letters with accents of a language (e.g. 'e' -> 'é è ê ë')
+/// The tables are plain-text files in the accents directory, one per language
+///
+/// Each line of a file contains a base letter followed by its variants, separated by SPACES and ordered by how often they are used.
+/// Empty lines and lines starting with '#' are ignored.
+pub struct AccentTable {
+    variants: HashMap<String, Vec<String>>,
+}
+
+impl AccentTable {
+    /// Load the accent table with the name (e.g. 'de' loads the file 'de.txt')
+    /// Returns 'None' if the table can not be read
+    pub fn load(name: &str) -> Option<AccentTable> {
+        let mut path = directories::get_absolute_path(directories::ACCENT_DIR_REL)?;
+        path.push(format!("{name}.txt"));
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) => {
+                error!(
+                    "Unable to read the accent table {:?}. Error description: {}",
+                    path, err
+                );
+                return None;
+            }
+        };
+        let mut variants = HashMap::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut letters = line.split_whitespace();
+            if let Some(base_letter) = letters.next() {
+                variants.insert(
+                    base_letter.to_lowercase(),
+                    letters.map(str::to_string).collect(),
+                );
+            }
+        }
+        info!("Loaded the accent table {:?}", path);
+        Some(AccentTable { variants })
+    }
+
+    /// Returns the popup for the key if its id is a letter with variants
+    /// The variants are in uppercase if the key id is an uppercase letter (e.g. in the caps view)
+    pub fn get_popup(&self, key_id: &str) -> Option<Vec<String>> {
+        let variants = self.variants.get(&key_id.to_lowercase())?;
+        let is_uppercase = key_id.chars().all(char::is_uppercase);
+        let variants: Vec<String> = variants
+            .iter()
+            .map(|variant| {
+                let uppercase_variant = variant.to_uppercase();
+                // Some letters don't have a single uppercase letter (e.g. 'ß' -> 'SS'). They are kept lowercase
+                if is_uppercase && uppercase_variant.chars().count() == variant.chars().count() {
+                    uppercase_variant
+                } else {
+                    variant.to_string()
+                }
+            })
+            .collect();
+        if variants.is_empty() {
+            None
+        } else {
+            Some(vec![variants.join(" ")])
+        }
+    }
+}
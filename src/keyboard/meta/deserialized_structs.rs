@@ -12,7 +12,7 @@ pub type KeyIds = String;
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
 #[serde(deny_unknown_fields)]
-/// Enumeration to differentiate a short from a long press
+/// Enumeration to differentiate a short from a long press, a double tap and flicks in the four directions
 /// There are no variants to make the definition of a layout simpler
 /// The KeyEvent will need to be translated
 pub enum KeyEvent {
@@ -20,6 +20,16 @@ pub enum KeyEvent {
     ShortPress,
     #[serde(rename = "long_press")]
     LongPress,
+    #[serde(rename = "double_tap")]
+    DoubleTap,
+    #[serde(rename = "swipe_up")]
+    SwipeUp,
+    #[serde(rename = "swipe_down")]
+    SwipeDown,
+    #[serde(rename = "swipe_left")]
+    SwipeLeft,
+    #[serde(rename = "swipe_right")]
+    SwipeRight,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
@@ -71,13 +81,61 @@ pub enum KeyAction {
     TempSwitchLayout(String),
 
     #[serde(rename = "erase")]
-    /// Erase the last char (NOT grapheme!)
+    /// Erase the last grapheme cluster (e.g. an emoji with a skin tone modifier is erased as a whole)
     Erase,
 
+    #[serde(rename = "erase_word")]
+    /// Erase the word before the cursor and the whitespace following it
+    ErasePreviousWord,
+
+    #[serde(rename = "erase_next")]
+    /// Erase the grapheme cluster after the cursor
+    EraseNextChar,
+
+    #[serde(rename = "erase_line")]
+    /// Erase the whole line the cursor is in
+    /// Nothing is erased if the application does not send the surrounding text
+    EraseLine,
+
     #[serde(rename = "open_popup")]
     /// Open the keys popup
     /// The content of the popup is defined by a different struct
     OpenPopup,
+
+    #[serde(rename = "multi_tap")]
+    /// Cycle through the characters with repeated taps (e.g. 'a b c 2' on a phone keypad)
+    /// The character is shown as preedit and committed after a timeout or when a different key is used
+    MultiTap(Vec<String>),
+
+    #[serde(rename = "undo")]
+    /// Undo the last text the keyboard committed or erased in the focused text field
+    Undo,
+
+    #[serde(rename = "redo")]
+    /// Redo the last edit that was undone
+    Redo,
+
+    #[serde(rename = "shortcut")]
+    #[serde(deserialize_with = "shortcut_from_str")]
+    /// Press the modifiers, enter the keycode and restore the previous state of the modifiers (e.g. 'ctrl+shift+t')
+    /// The modifiers and the keycode are separated by '+'. The keycode is looked up like the one of EnterKeycode
+    Shortcut(Vec<Modifier>, u32),
+
+    #[serde(rename = "sequence")]
+    /// Execute several steps in order. A step is a key action, a keycode (e.g. 'TAB') or a delay in milliseconds (e.g. 'delay: 100')
+    Sequence(Vec<SequenceStep>),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(untagged)]
+/// A single step of a KeyAction::Sequence
+pub enum SequenceStep {
+    /// Enter the keycode
+    Keycode(#[serde(deserialize_with = "from_str")] u32),
+    /// Wait for the milliseconds before the next step is executed
+    Delay { delay: u64 },
+    /// Execute the key action
+    Action(KeyAction),
 }
 
 /// Tries to look up the numeric value of a keycode. If it is not valid, return an error
@@ -94,14 +152,42 @@ where
         })
 }
 
+/// Tries to split a shortcut (e.g. 'ctrl+shift+t') into its modifiers and the numeric value of its keycode. If it is not valid, return an error
+fn shortcut_from_str<'de, D>(deserializer: D) -> Result<(Vec<Modifier>, u32), D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let shortcut: String = Deserialize::deserialize(deserializer)?;
+    let mut parts: Vec<&str> = shortcut.split('+').map(str::trim).collect();
+    let keycode_str = parts.pop().unwrap_or_default().to_ascii_uppercase();
+    let keycode = input_event_codes_hashmap::KEY
+        .get::<str>(&keycode_str)
+        .ok_or_else(|| Error::custom("Not a valid keycode"))?;
+    let mut modifiers = Vec::new();
+    for part in parts {
+        let modifier = match part.to_ascii_lowercase().as_str() {
+            "shift" => Modifier::Shift,
+            "ctrl" | "control" => Modifier::Control,
+            "alt" => Modifier::Alt,
+            "super" | "meta" | "logo" => Modifier::Mod4,
+            "altgr" => Modifier::Mod5,
+            _ => return Err(Error::custom(format!("Not a valid modifier: {part}"))),
+        };
+        modifiers.push(modifier);
+    }
+    Ok((modifiers, *keycode))
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
 #[serde(deny_unknown_fields)]
-/// Keys can display a text or they can show an image
+/// Keys can display a text, show an image or both
 pub enum KeyDisplay {
     #[serde(rename = "text")]
     Text(String),
     #[serde(rename = "image")]
     Image(String),
+    #[serde(rename = "text_and_image")]
+    TextAndImage { text: String, image: String },
 }
 
 #[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
@@ -145,6 +231,34 @@ pub struct KeyDeserialized {
     pub outline: Option<Outline>,                           // The dimension of the key (width)
     pub popup: Option<Vec<String>>, // The content of a popover that can be opened
     pub styles: Option<Vec<String>>, // Style classes that can get attatched to the key to easily style it
+    pub hint: Option<String>,        // Small label in the corner of the key
+    pub long_press_delay: Option<u64>, // Milliseconds the key needs to be held for a long press
+    pub repeat: Option<bool>, // Repeat the short press while the key is held down (e.g. for arrow keys)
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+/// What a long press on a letter does if the layout does not define the long press of the key
+pub enum LetterLongPress {
+    /// Enter the uppercase letter
+    #[default]
+    #[serde(rename = "uppercase")]
+    Uppercase,
+    /// Open the popup of the key under the finger. The entry the finger slides to is entered when the finger is lifted
+    #[serde(rename = "popup")]
+    Popup,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(deny_unknown_fields)]
+/// Engines that compose the text entered by the keys before it is committed
+pub enum InputEngine {
+    /// Combine the jamo of a 2-set Korean layout to syllable blocks
+    #[serde(rename = "hangul")]
+    Hangul,
+    /// Show the typed pinyin as preedit and the Hanzi it can be written with in the suggestion bar
+    #[serde(rename = "pinyin")]
+    Pinyin,
 }
 
 #[derive(Debug, Deserialize, PartialEq, Eq)]
@@ -155,6 +269,37 @@ pub struct LayoutDeserialized {
     #[serde(rename = "buttons")]
     // Purism calls it buttons, renamed so users need to change less when using a layout from squeekboard
     pub keys: HashMap<String, KeyDeserialized>,
+    #[serde(default)]
+    pub letter_long_press: LetterLongPress,
+    #[serde(default)]
+    // Name of the table to automatically attach popups with letters with accents to the letters
+    pub accents: Option<String>,
+    #[serde(default)]
+    // Show hints on keys with a long press that enters a string or with a popup
+    pub show_hints: bool,
+    #[serde(default = "enabled")]
+    // Show a magnified preview of a pressed key above it
+    pub key_preview: bool,
+    #[serde(default)]
+    // Milliseconds a key needs to be held for a long press, unless the key defines its own delay
+    pub long_press_delay: Option<u64>,
+    #[serde(default)]
+    // Name of the view the keyboard switches to for a single letter when it is capitalized automatically (e.g. at the start of a sentence)
+    pub shifted_view: Option<String>,
+    #[serde(default)]
+    // Keys are pressed as long as they are touched and several keys can be held at once (e.g. for game pads)
+    pub direct: bool,
+    #[serde(default)]
+    // Latched modifiers are released after the next key is entered
+    pub sticky_modifiers: bool,
+    #[serde(default)]
+    // Engine that composes the strings entered by the keys (e.g. to combine Korean jamo to syllables or to choose the Hanzi for pinyin)
+    pub input_engine: Option<InputEngine>,
+}
+
+/// Default value of settings that are enabled unless the layout disables them
+fn enabled() -> bool {
+    true
 }
 
 impl LayoutDeserialized {
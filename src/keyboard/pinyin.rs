@@ -0,0 +1,173 @@
+// Imports from other crates
+use std::collections::BTreeMap;
+use std::fs;
+
+// Imports from other modules
+use crate::config::directories;
+use crate::config::input_settings::MAX_PINYIN_CANDIDATES;
+
+/// The PinyinComposer collects the typed pinyin letters and looks up the Hanzi they can be written with
+///
+/// The dictionary is a plain-text file. Each line contains the pinyin without tones followed by the words it is written with, separated by SPACES and ordered by how often they are used.
+/// Empty lines and lines starting with '#' are ignored.
+/// The candidates for the letters are the words for all of them, then the words whose pinyin starts with them and then the words for the first letters.
+/// If one of the last ones is chosen, the remaining letters stay composed
+pub struct PinyinComposer {
+    dictionary: BTreeMap<String, Vec<String>>,
+    letters: String,
+    candidates: Vec<(String, usize)>, // The words that can be chosen and the number of letters each of them replaces
+}
+
+impl PinyinComposer {
+    /// Load the dictionary and create a PinyinComposer that does not compose any letters
+    /// Returns 'None' if the dictionary can not be read
+    pub fn load() -> Option<PinyinComposer> {
+        let path = directories::get_absolute_path(directories::PINYIN_DICTIONARY_FILE_REL)?;
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) => {
+                error!(
+                    "Unable to read the pinyin dictionary {:?}. Error description: {}",
+                    path, err
+                );
+                return None;
+            }
+        };
+        let mut dictionary: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut entries = line.split_whitespace();
+            if let Some(pinyin) = entries.next() {
+                dictionary
+                    .entry(pinyin.to_lowercase())
+                    .or_default()
+                    .extend(entries.map(str::to_string));
+            }
+        }
+        info!(
+            "Loaded the pinyin dictionary {:?} with {} syllables and words",
+            path,
+            dictionary.len()
+        );
+        Some(PinyinComposer {
+            dictionary,
+            letters: String::new(),
+            candidates: Vec::new(),
+        })
+    }
+
+    /// Returns true if the letter can be part of the pinyin
+    pub fn is_pinyin_letter(character: char) -> bool {
+        character.is_ascii_lowercase()
+    }
+
+    /// Returns true while letters are composed
+    pub fn is_composing(&self) -> bool {
+        !self.letters.is_empty()
+    }
+
+    /// Returns the letters that are composed, so they can be shown as preedit
+    pub fn get_preedit(&self) -> String {
+        self.letters.to_string()
+    }
+
+    /// Returns the words the composed letters can be written with
+    pub fn get_candidates(&self) -> Vec<String> {
+        self.candidates
+            .iter()
+            .map(|(candidate, _)| candidate.to_string())
+            .collect()
+    }
+
+    /// Adds the letter to the pinyin
+    pub fn input(&mut self, letter: char) {
+        self.letters.push(letter);
+        self.update_candidates();
+    }
+
+    /// Removes the letter that was typed last
+    /// Returns false if no letters were composed
+    pub fn backspace(&mut self) -> bool {
+        if self.letters.pop().is_none() {
+            return false;
+        }
+        self.update_candidates();
+        true
+    }
+
+    /// Returns the candidate if it is one of the candidates and removes the letters it replaces
+    pub fn choose(&mut self, candidate: &str) -> Option<String> {
+        let no_letters = self
+            .candidates
+            .iter()
+            .find(|(word, _)| word == candidate)
+            .map(|(_, no_letters)| *no_letters)?;
+        self.letters.drain(..no_letters);
+        self.update_candidates();
+        Some(candidate.to_string())
+    }
+
+    /// Chooses the first candidate
+    /// Returns 'None' if there is no candidate
+    pub fn choose_first(&mut self) -> Option<String> {
+        let (candidate, _) = self.candidates.first()?.clone();
+        self.choose(&candidate)
+    }
+
+    /// Returns the composed letters as they are and stops composing them
+    pub fn commit(&mut self) -> String {
+        let letters = std::mem::take(&mut self.letters);
+        self.candidates.clear();
+        letters
+    }
+
+    /// Forgets the composed letters without returning them, e.g. because their preedit was already committed
+    pub fn reset(&mut self) {
+        self.letters.clear();
+        self.candidates.clear();
+    }
+
+    /// Looks up the candidates for the composed letters
+    fn update_candidates(&mut self) {
+        let mut candidates: Vec<(String, usize)> = Vec::new();
+        let letters = &self.letters;
+        if letters.is_empty() {
+            self.candidates = candidates;
+            return;
+        }
+        let all_letters = self.dictionary.get(letters).into_iter();
+        // The last syllable might not be typed completely yet
+        let completions = self
+            .dictionary
+            .range(letters.to_string()..)
+            .take_while(|(pinyin, _)| pinyin.starts_with(letters.as_str()))
+            .filter(|(pinyin, _)| pinyin.len() > letters.len())
+            .map(|(_, words)| words);
+        for words in all_letters.chain(completions) {
+            candidates.extend(words.iter().map(|word| (word.to_string(), letters.len())));
+        }
+        // Words for the first syllables. The longest match comes first
+        for no_letters in (1..letters.len()).rev() {
+            if let Some(words) = self.dictionary.get(&letters[..no_letters]) {
+                candidates.extend(words.iter().map(|word| (word.to_string(), no_letters)));
+            }
+        }
+        // A word is only suggested once, for as many letters as possible
+        let mut unique_candidates: Vec<(String, usize)> = Vec::new();
+        for candidate in candidates {
+            if !unique_candidates
+                .iter()
+                .any(|(word, _)| *word == candidate.0)
+            {
+                unique_candidates.push(candidate);
+            }
+            if unique_candidates.len() == MAX_PINYIN_CANDIDATES {
+                break;
+            }
+        }
+        self.candidates = unique_candidates;
+    }
+}
@@ -1,4 +1,5 @@
 // Imports from other crates
+use std::sync::mpsc;
 use wayland_protocols::unstable::text_input::v3::client::zwp_text_input_v3::{
     ContentHint, ContentPurpose,
 };
@@ -13,26 +14,50 @@ use crate::user_interface::Win;
 /// It is used by the input_method service to notify the UI about requested changes to the visibility or content hint/purpose
 pub struct UIConnector {
     message_pipe: relm::Relm<crate::user_interface::Win>,
+    content_transmitter: Option<mpsc::Sender<Option<(String, String)>>>, // Tells the decoder that the surrounding text is unknown when the focus changes
 }
 
 impl UIConnector {
     /// Creates a new UIConnector
     pub fn new(message_pipe: relm::Relm<Win>) -> UIConnector {
-        UIConnector { message_pipe }
+        UIConnector {
+            message_pipe,
+            content_transmitter: None,
+        }
+    }
+    // Tell the decoder about focus changes through the channel of the surrounding text
+    pub fn set_content_transmitter(&mut self, transmitter: mpsc::Sender<Option<(String, String)>>) {
+        self.content_transmitter = Some(transmitter);
+    }
+    // The text of the previous text field is outdated. The message is sent before the surrounding text of the new text field, so the new text is not lost
+    fn forget_surrounding_text(&self) {
+        if let Some(transmitter) = &self.content_transmitter {
+            let _ = transmitter.send(None);
+        }
     }
     // Send the message to the UI
     pub fn emit(&self, msg: Msg) {
         self.message_pipe.stream().emit(msg)
     }
+    // Send the message to the UI after the delay (in milliseconds) passed
+    pub fn emit_after<F: Fn() -> Msg + 'static>(&self, delay: u64, msg: F) {
+        relm::timeout(
+            self.message_pipe.stream(),
+            delay.try_into().unwrap_or(u32::MAX),
+            msg,
+        );
+    }
 }
 
 /// Implements the KeyboardVisibility trait from the zwp_input_method_service crate to notify the UI about requested changes to the visibility
 impl IMVisibility for UIConnector {
     fn activate_im(&self) {
+        self.forget_surrounding_text();
         self.emit(Msg::SetVisibility(true));
         info!("Requested to show the keyboard");
     }
     fn deactivate_im(&self) {
+        self.forget_surrounding_text();
         self.emit(Msg::SetVisibility(false));
         info!("Requested to hide the keyboard");
     }
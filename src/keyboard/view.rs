@@ -3,6 +3,7 @@ use std::collections::HashMap;
 
 // Imports from other modules
 use crate::keyboard::{Key, KeyArrangement, KeyMeta};
+use crate::privacy::redact;
 
 #[derive(Debug)]
 /// The view contains all its keys and their location in a two-dimensional space.
@@ -86,7 +87,7 @@ impl View {
             }
         }
 
-        info!("Closest key {:?}", closest_key);
+        info!("Closest key {}", redact(format!("{:?}", closest_key)));
         closest_key
     }
 
@@ -103,6 +104,32 @@ impl View {
         }
     }
 
+    /// Gets the center of each key whose id is a single char
+    /// Wide keys occupy several cells so the center is the average of the cells coordinates
+    pub fn get_key_centers(&self) -> HashMap<char, (f64, f64)> {
+        let mut summed_coordinates: HashMap<char, (f64, f64, f64)> = HashMap::new();
+        for ((x, y), key) in &self.key_coordinates {
+            let key_id = key.get_id();
+            let mut chars = key_id.chars();
+            if let (Some(character), None) = (chars.next(), chars.next()) {
+                for character in character.to_lowercase() {
+                    let sum = summed_coordinates
+                        .entry(character)
+                        .or_insert((0.0, 0.0, 0.0));
+                    sum.0 += x;
+                    sum.1 += y;
+                    sum.2 += 1.0;
+                }
+            }
+        }
+        summed_coordinates
+            .into_iter()
+            .map(|(character, (x_sum, y_sum, no_cells))| {
+                (character, (x_sum / no_cells, y_sum / no_cells))
+            })
+            .collect()
+    }
+
     pub fn get_row_to_column_ratio(&self) -> f64 {
         self.row_to_column_ratio
     }
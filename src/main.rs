@@ -31,6 +31,7 @@ mod config;
 mod dbus;
 mod decoder;
 mod keyboard;
+mod privacy;
 mod submitter;
 mod user_interface;
 
@@ -43,8 +44,39 @@ fn get_locale_language() -> String {
     locale_language.to_lowercase()
 }
 
+/// Imports or exports the user dictionary of the language if it was requested with a command line argument
+/// Returns 'true' if the arguments were handled and the keyboard does not need to be started
+fn handle_dictionary_args(args: &[String]) -> bool {
+    let (option, path) = match args {
+        [_, option, path] if option == "--import-dictionary" || option == "--export-dictionary" => {
+            (option, std::path::Path::new(path))
+        }
+        _ => return false,
+    };
+    let mut user_dictionary = decoder::UserDictionary::load(&get_locale_language());
+    let result = if option == "--import-dictionary" {
+        user_dictionary
+            .import(path)
+            .map(|no_words| println!("Imported {no_words} words from {}", path.display()))
+    } else {
+        user_dictionary
+            .export(path)
+            .map(|_| println!("Exported the user dictionary to {}", path.display()))
+    };
+    if let Err(err) = result {
+        eprintln!("Unable to access {}: {}", path.display(), err);
+        std::process::exit(1);
+    }
+    true
+}
+
 /// Initiates the logger and starts the main loop
 fn main() {
     pretty_env_logger::init();
+    privacy::init();
+    let args: Vec<String> = std::env::args().collect();
+    if handle_dictionary_args(&args) {
+        return;
+    }
     user_interface::Win::run(()).unwrap();
 }
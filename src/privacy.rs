@@ -0,0 +1,57 @@
+// Imports from other crates
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Imports from other modules
+use crate::config::input_settings::REDACT_TYPED_TEXT_ENV;
+
+/// Set while the focused text input is a sensitive field (e.g. a password field)
+static SENSITIVE_FIELD: AtomicBool = AtomicBool::new(false);
+/// Set if the user requested typed text to always be redacted from the logs
+static ALWAYS_REDACT: AtomicBool = AtomicBool::new(false);
+
+/// Reads the global setting to redact typed text from the logs everywhere
+pub fn init() {
+    let always_redact = envmnt::is_or(REDACT_TYPED_TEXT_ENV, false);
+    ALWAYS_REDACT.store(always_redact, Ordering::SeqCst);
+    info!(
+        "Typed text is always redacted from the logs: {}",
+        always_redact
+    );
+}
+
+/// Changes whether the focused text input is a sensitive field
+/// While it is, typed text is redacted from the logs and no words are learned or suggested
+pub fn set_sensitive_field(sensitive: bool) {
+    SENSITIVE_FIELD.store(sensitive, Ordering::SeqCst);
+    info!("Sensitive field focused: {}", sensitive);
+}
+
+/// Returns 'true' if the focused text input is a sensitive field
+pub fn is_sensitive_field() -> bool {
+    SENSITIVE_FIELD.load(Ordering::SeqCst)
+}
+
+/// Returns 'true' if typed text must not show up in the logs
+pub fn is_redacting() -> bool {
+    is_sensitive_field() || ALWAYS_REDACT.load(Ordering::SeqCst)
+}
+
+/// Wraps the typed text so that it is only written to the logs if that is allowed
+pub fn redact<T: fmt::Display>(text: T) -> Redacted<T> {
+    Redacted(text)
+}
+
+/// Typed text that is replaced by a placeholder when it is printed while the logs are redacted
+pub struct Redacted<T: fmt::Display>(T);
+
+impl<T: fmt::Display> fmt::Display for Redacted<T> {
+    /// Prints the text or the placeholder
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if is_redacting() {
+            write!(f, "<redacted>")
+        } else {
+            self.0.fmt(f)
+        }
+    }
+}
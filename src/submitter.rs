@@ -7,9 +7,21 @@ use zwp_input_method_service::{HintPurpose, IMService, IMVisibility, ReceiveSurr
 // Imports from other modules
 pub use self::wayland::vk_service::KeyMotion;
 use crate::keyboard;
+use crate::privacy::redact;
 
 // Modules
+mod history;
 pub mod wayland;
+use history::History;
+
+// Keycodes of the keys the keyboard submits itself
+pub const KEY_BACKSPACE: u32 = 14;
+pub const KEY_TAB: u32 = 15;
+pub const KEY_ENTER: u32 = 28;
+pub const KEY_SPACE: u32 = 57;
+pub const KEY_LEFT: u32 = 105;
+pub const KEY_RIGHT: u32 = 106;
+pub const KEY_DELETE: u32 = 111;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 /// Possible types of submissions
@@ -22,8 +34,17 @@ pub enum Submission {
     ToggleKeycode(u32),
     /// Emulates a modifier getting pressed (e.g SHIFT)
     Modifier(keyboard::Modifier),
-    /// Erase the number of chars before the cursor
-    Erase(u32),
+    /// Erase the string directly before the cursor
+    Erase(String),
+    /// Erase the string directly after the cursor
+    EraseAfter(String),
+    /// Emulates a physical key that is pressed and released while the modifiers are held
+    Shortcut(Vec<keyboard::Modifier>, u32),
+    /// Emulates a physical key that is either pressed or released
+    KeyMotion(u32, KeyMotion),
+    /// Show the text at the cursor as preedit (text that is being composed and not committed yet, e.g. shown underlined)
+    /// The cursor is the number of chars of the text before the cursor. An empty text removes the preedit
+    Preedit { text: String, cursor: usize },
 }
 
 /// Handles all submissions
@@ -31,6 +52,9 @@ pub struct Submitter<T: 'static + IMVisibility + HintPurpose, D: 'static + Recei
     event_queue: EventQueue,
     im_service: Option<IMService<T, D>>,
     virtual_keyboard: Option<Arc<Mutex<wayland::vk_service::VKService>>>,
+    preedit: String,     // The text that is shown as preedit
+    preedit_typed: bool, // True if the preedit was typed with the virtual_keyboard protocol because the input_method protocol is not available
+    history: History,    // The text committed and erased in the focused text field
 }
 
 impl<T: IMVisibility + HintPurpose, D: ReceiveSurroundingText> Submitter<T, D> {
@@ -60,6 +84,9 @@ impl<T: IMVisibility + HintPurpose, D: ReceiveSurroundingText> Submitter<T, D> {
             event_queue,
             im_service,
             virtual_keyboard,
+            preedit: String::new(),
+            preedit_typed: false,
+            history: History::new(),
         }
     }
 
@@ -89,6 +116,84 @@ impl<T: IMVisibility + HintPurpose, D: ReceiveSurroundingText> Submitter<T, D> {
         }
     }
 
+    /// Returns true if a modifier is pressed that applications only apply to keys (e.g. CTRL)
+    pub fn has_command_modifiers(&self) -> bool {
+        self.virtual_keyboard
+            .as_ref()
+            .map_or(false, |virtual_keyboard| {
+                virtual_keyboard.lock().unwrap().has_command_modifiers()
+            })
+    }
+
+    /// Sends requests to release all modifiers
+    pub fn release_all_modifiers(&mut self) {
+        if let Some(virtual_keyboard) = &mut self.virtual_keyboard {
+            if virtual_keyboard
+                .lock()
+                .unwrap()
+                .release_all_modifiers()
+                .is_err()
+            {
+                error!("Submitter failed to release all modifiers");
+            }
+        }
+    }
+
+    /// Sends requests to release all keys. The modifiers stay latched
+    pub fn release_all_keys(&mut self) {
+        if let Some(virtual_keyboard) = &mut self.virtual_keyboard {
+            if virtual_keyboard.lock().unwrap().release_all_keys().is_err() {
+                error!("Submitter failed to release all keys");
+            }
+        }
+    }
+
+    /// Forget the edits of the previously focused text field
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    /// Undo the last edit if the text before the cursor still matches it
+    /// Returns the text that was erased and the text that was committed instead
+    pub fn undo(&mut self, text_left_of_cursor: &str) -> Option<(String, String)> {
+        let (text_to_erase, text_to_commit) = self.history.undo(text_left_of_cursor)?;
+        info!("Submitter is trying to undo the last edit");
+        self.replace_text_before_cursor(&text_to_erase, &text_to_commit)
+    }
+
+    /// Redo the last edit that was undone if the text before the cursor still matches it
+    /// Returns the text that was erased and the text that was committed instead
+    pub fn redo(&mut self, text_left_of_cursor: &str) -> Option<(String, String)> {
+        let (text_to_erase, text_to_commit) = self.history.redo(text_left_of_cursor)?;
+        info!("Submitter is trying to redo the last edit that was undone");
+        self.replace_text_before_cursor(&text_to_erase, &text_to_commit)
+    }
+
+    /// Forget the preedit because the input method was deactivated
+    /// The compositor already removed it from the text field that lost the focus and ignores commits until the input method is activated again.
+    /// A preedit that was typed with the virtual_keyboard protocol stays part of the text
+    pub fn cancel_preedit(&mut self) {
+        if self.preedit.is_empty() {
+            return;
+        }
+        let preedit = std::mem::take(&mut self.preedit);
+        if std::mem::take(&mut self.preedit_typed) {
+            info!("Submitter keeps the typed preedit: {}", redact(&preedit));
+        } else {
+            info!(
+                "The input method was deactivated. Submitter cancelled the preedit: {}",
+                redact(&preedit)
+            );
+        }
+    }
+
+    /// Remove the preedit without committing it
+    pub fn clear_preedit(&mut self) {
+        if !self.preedit.is_empty() {
+            self.set_preedit("", 0);
+        }
+    }
+
     /// Submits the Submission with the available protocol.
     /// If available the input_method protocol is tried first because it is simpler to submit strings
     /// and more reliable when it comes to submitting non-ascii chars
@@ -99,7 +204,10 @@ impl<T: IMVisibility + HintPurpose, D: ReceiveSurroundingText> Submitter<T, D> {
                 self.submit_text(&text);
             }
             Submission::Keycode(keycode) => {
-                info!("Submitter is trying to submit the keycode: {}", keycode);
+                info!(
+                    "Submitter is trying to submit the keycode: {}",
+                    redact(keycode)
+                );
                 if let Some(virtual_keyboard) = &mut self.virtual_keyboard {
                     if virtual_keyboard
                         .lock()
@@ -109,18 +217,25 @@ impl<T: IMVisibility + HintPurpose, D: ReceiveSurroundingText> Submitter<T, D> {
                     {
                         error!(
                             "Submitter failed to press and release the keycode {}",
-                            keycode
+                            redact(keycode)
                         );
                     }
+                }
+                // Without the virtual_keyboard protocol, keys that enter a character are submitted as text
+                else if let Some(text) = text_of_keycode(keycode) {
+                    self.submit_text(text);
                 } else {
                     error!(
                         "Virtual_keyboard protocol not available! Unable to submit keycode {}",
-                        keycode
+                        redact(keycode)
                     )
                 };
             }
             Submission::ToggleKeycode(keycode) => {
-                info!("Submitter is trying to toggle the keycode: {}", keycode);
+                info!(
+                    "Submitter is trying to toggle the keycode: {}",
+                    redact(keycode)
+                );
                 if let Some(virtual_keyboard) = &mut self.virtual_keyboard {
                     if virtual_keyboard
                         .lock()
@@ -128,12 +243,12 @@ impl<T: IMVisibility + HintPurpose, D: ReceiveSurroundingText> Submitter<T, D> {
                         .toggle_key(keycode)
                         .is_err()
                     {
-                        error!("Submitter failed to toggle the keycode {}", keycode);
+                        error!("Submitter failed to toggle the keycode {}", redact(keycode));
                     }
                 } else {
                     error!(
                         "Virtual_keyboard protocol not available! Unable to toggle keycode {}",
-                        keycode
+                        redact(keycode)
                     )
                 };
             }
@@ -155,17 +270,116 @@ impl<T: IMVisibility + HintPurpose, D: ReceiveSurroundingText> Submitter<T, D> {
                     error!("Virtual_keyboard protocol not available! Unable to toggle modifier")
                 };
             }
-            Submission::Erase(no_char) => {
-                self.erase(no_char);
+            Submission::Erase(text) => {
+                self.erase(&text, "");
+            }
+            Submission::EraseAfter(text) => {
+                self.erase("", &text);
+            }
+            Submission::Shortcut(modifiers, keycode) => {
+                info!(
+                    "Submitter is trying to submit the keycode {} with the modifiers {:?}",
+                    redact(keycode),
+                    modifiers
+                );
+                if let Some(virtual_keyboard) = &mut self.virtual_keyboard {
+                    if virtual_keyboard
+                        .lock()
+                        .unwrap()
+                        .press_release_key_with_modifiers(keycode, modifiers)
+                        .is_err()
+                    {
+                        error!("Submitter failed to submit the shortcut");
+                    }
+                } else {
+                    error!("Virtual_keyboard protocol not available! Unable to submit shortcut")
+                };
+            }
+            Submission::Preedit { text, cursor } => {
+                self.set_preedit(&text, cursor);
+            }
+            Submission::KeyMotion(keycode, key_motion) => {
+                info!(
+                    "Submitter is trying to send {:?} of the keycode {}",
+                    key_motion,
+                    redact(keycode)
+                );
+                if let Some(virtual_keyboard) = &mut self.virtual_keyboard {
+                    if virtual_keyboard
+                        .lock()
+                        .unwrap()
+                        .send_key(keycode, key_motion)
+                        .is_err()
+                    {
+                        error!("Submitter failed to send the key motion");
+                    }
+                } else {
+                    error!("Virtual_keyboard protocol not available! Unable to send key motion")
+                };
+            }
+        }
+    }
+
+    /// Show the text as preedit with the cursor after the number of chars
+    /// If the input_method protocol is not available, the preedit is typed with the virtual_keyboard protocol instead.
+    /// A typed preedit is erased with BackSpace when it changes, so it can not be underlined and the cursor is always at its end
+    fn set_preedit(&mut self, text: &str, cursor: usize) {
+        info!("Submitter is trying to show the preedit: {}", redact(text));
+        if let Some(im) = &mut self.im_service {
+            let cursor = text
+                .char_indices()
+                .nth(cursor)
+                .map_or(text.len(), |(byte_index, _)| byte_index);
+            let cursor: i32 = cursor.try_into().unwrap();
+            if im
+                .set_preedit_string(text.to_string(), cursor, cursor)
+                .is_ok()
+                && im.commit().is_ok()
+            {
+                self.preedit = text.to_string();
+                return;
+            }
+        }
+
+        if let Some(virtual_keyboard) = &mut self.virtual_keyboard {
+            let mut virtual_keyboard = virtual_keyboard.lock().unwrap();
+            let no_typed_chars = if self.preedit_typed {
+                self.preedit.chars().count()
+            } else {
+                0
+            };
+            let erased = (0..no_typed_chars)
+                .all(|_| virtual_keyboard.press_release_key(KEY_BACKSPACE).is_ok());
+            if erased && (text.is_empty() || virtual_keyboard.send_unicode_str(text).is_ok()) {
+                self.preedit = text.to_string();
+                self.preedit_typed = !text.is_empty();
+                return;
             }
         }
+
+        error!("Submitter failed to show the preedit");
     }
 
     /// Try to submit the text
     /// If the input_method protocol is available, use it to submit the string as a whole.
     /// If it is not available, submit each character individually via virtual_keyboard protocol (This is error prone and should only be used as a last resort).
     fn submit_text(&mut self, text: &str) {
-        info!("Submitter is trying to submit the text: {}", text);
+        info!("Submitter is trying to submit the text: {}", redact(text));
+        // Applications only apply modifiers like CTRL to keys, so the text is entered with keys while they are pressed (e.g. CTRL + 'c')
+        if let Some(virtual_keyboard) = &mut self.virtual_keyboard {
+            let mut virtual_keyboard = virtual_keyboard.lock().unwrap();
+            if virtual_keyboard.has_command_modifiers()
+                && virtual_keyboard.send_str_as_keys(text).is_ok()
+            {
+                // The key combination can change the text in any way (e.g. CTRL + 'v'), so the edits can no longer be verified
+                self.history.clear();
+                return;
+            }
+        }
+        self.history.record_commit(text);
+        // Committing text removes the preedit. A typed preedit stays part of the text
+        self.preedit.clear();
+        self.preedit_typed = false;
         if let Some(im) = &mut self.im_service {
             if im.commit_string(text.to_string()).is_ok() && im.commit().is_ok() {
                 return;
@@ -184,19 +398,36 @@ impl<T: IMVisibility + HintPurpose, D: ReceiveSurroundingText> Submitter<T, D> {
             }
         }
 
-        error!("Failed to submit the text: {}", text);
+        error!("Failed to submit the text: {}", redact(text));
     }
 
-    /// Erases the specified amount of chars left of the cursor
-    /// Uses the input_method protocol if available. As a fallback it sends press/release requests of the DELETE key repeatedly.
-    fn erase(&mut self, no_char: u32) {
+    /// Erases the text left and right of the cursor
+    /// Uses the input_method protocol if available, which expects the length of the texts in bytes.
+    /// As a fallback it sends press/release requests of the BACKSPACE key once for each char of the text left of the cursor
+    /// and of the DELETE key once for each char of the text right of the cursor.
+    fn erase(&mut self, text_before: &str, text_after: &str) {
+        let no_bytes_before: u32 = text_before.len().try_into().unwrap();
+        let no_bytes_after: u32 = text_after.len().try_into().unwrap();
+        let no_char_before = text_before.chars().count();
+        let no_char_after = text_after.chars().count();
         info!(
-            "Submitter is trying to erase the last {} characters",
-            no_char
+            "Submitter is trying to erase {} characters before and {} characters after the cursor",
+            no_char_before, no_char_after
         );
+        self.preedit.clear();
+        self.preedit_typed = false;
+        // Text after the cursor is not part of the history, so the edits before it can no longer be verified
+        if text_after.is_empty() {
+            self.history.record_erase(text_before);
+        } else {
+            self.history.clear();
+        }
         if let Some(im) = &self.im_service {
             if im
-                .delete_surrounding_text(no_char.try_into().unwrap(), 0)
+                .delete_surrounding_text(
+                    no_bytes_before.try_into().unwrap(),
+                    no_bytes_after.try_into().unwrap(),
+                )
                 .is_ok()
                 && im.commit().is_ok()
             {
@@ -206,12 +437,14 @@ impl<T: IMVisibility + HintPurpose, D: ReceiveSurroundingText> Submitter<T, D> {
         }
 
         if let Some(virtual_keyboard) = &mut self.virtual_keyboard {
-            for _ in 0..no_char {
-                // Keycode for 'DELETE is 111
+            let keycodes = std::iter::repeat(KEY_BACKSPACE)
+                .take(no_char_before)
+                .chain(std::iter::repeat(KEY_DELETE).take(no_char_after));
+            for keycode in keycodes {
                 if virtual_keyboard
                     .lock()
                     .unwrap()
-                    .press_release_key(111)
+                    .press_release_key(keycode)
                     .is_err()
                 {
                     break;
@@ -224,4 +457,36 @@ impl<T: IMVisibility + HintPurpose, D: ReceiveSurroundingText> Submitter<T, D> {
 
         error!("Submitter failed to erase the characters");
     }
+
+    /// Erases the text before the cursor and commits the other text in its place with a single commit of the input_method protocol
+    /// The edit is not recorded in the history because it is part of undoing or redoing an edit
+    fn replace_text_before_cursor(
+        &mut self,
+        text_to_erase: &str,
+        text_to_commit: &str,
+    ) -> Option<(String, String)> {
+        if let Some(im) = &self.im_service {
+            let no_bytes_before: u32 = text_to_erase.len().try_into().unwrap();
+            if im
+                .delete_surrounding_text(no_bytes_before.try_into().unwrap(), 0)
+                .is_ok()
+                && im.commit_string(text_to_commit.to_string()).is_ok()
+                && im.commit().is_ok()
+            {
+                return Some((text_to_erase.to_string(), text_to_commit.to_string()));
+            }
+        }
+        error!("Input_method protocol not available! Unable to replace the text before the cursor");
+        None
+    }
+}
+
+/// Returns the text a key enters, if it enters a character
+fn text_of_keycode(keycode: u32) -> Option<&'static str> {
+    match keycode {
+        KEY_TAB => Some("\t"),
+        KEY_ENTER => Some("\n"),
+        KEY_SPACE => Some(" "),
+        _ => None,
+    }
 }
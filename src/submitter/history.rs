@@ -0,0 +1,138 @@
+// Imports from other modules
+use crate::privacy::{self, redact};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An edit replaced the text directly before the cursor with a different text
+/// Typing only inserts text and erasing only erases text, but e.g. a correction does both
+struct Edit {
+    erased: String,
+    inserted: String,
+}
+
+/// The History remembers the text the keyboard committed and erased in the focused text field, so the edits can be undone and redone
+/// The text typed for a word is one edit. Nothing is remembered in sensitive fields (e.g. password fields)
+pub struct History {
+    edits: Vec<Edit>,
+    position: usize, // Number of edits that are not undone
+    sealed: bool,    // True if the next commit or erasure starts a new edit
+}
+
+impl History {
+    /// Creates a new empty History
+    pub fn new() -> History {
+        History {
+            edits: Vec::new(),
+            position: 0,
+            sealed: true,
+        }
+    }
+
+    /// Forget all edits, e.g. because a different text field was focused
+    pub fn clear(&mut self) {
+        if !self.edits.is_empty() {
+            info!("The history of the edits was cleared");
+        }
+        self.edits.clear();
+        self.position = 0;
+        self.sealed = true;
+    }
+
+    /// Remember that the text was committed at the cursor
+    pub fn record_commit(&mut self, text: &str) {
+        if text.is_empty() || privacy::is_sensitive_field() {
+            return;
+        }
+        match self.get_open_edit() {
+            Some(edit) => edit.inserted.push_str(text),
+            None => self.edits.push(Edit {
+                erased: String::new(),
+                inserted: text.to_string(),
+            }),
+        }
+        self.position = self.edits.len();
+        // The next word is a separate edit
+        self.sealed = text.ends_with(char::is_whitespace);
+    }
+
+    /// Remember that the text directly before the cursor was erased
+    pub fn record_erase(&mut self, text: &str) {
+        if text.is_empty() || privacy::is_sensitive_field() {
+            return;
+        }
+        let mut merged = false;
+        if let Some(edit) = self.get_open_edit() {
+            // The text that was just typed is corrected
+            if edit.inserted.ends_with(text) {
+                let new_len = edit.inserted.len() - text.len();
+                edit.inserted.truncate(new_len);
+                merged = true;
+            }
+            // Erasing continues
+            else if edit.inserted.is_empty() {
+                edit.erased.insert_str(0, text);
+                merged = true;
+            }
+        }
+        if !merged {
+            self.edits.push(Edit {
+                erased: text.to_string(),
+                inserted: String::new(),
+            });
+        }
+        // Edits that were corrected until nothing is left are forgotten
+        if self.edits.last().map_or(false, |edit| {
+            edit.erased.is_empty() && edit.inserted.is_empty()
+        }) {
+            self.edits.pop();
+        }
+        self.position = self.edits.len();
+        self.sealed = false;
+    }
+
+    /// Returns the text to erase before the cursor and the text to commit instead to undo the last edit
+    /// Returns 'None' if there is nothing to undo or if the text before the cursor no longer matches the edit. Then the text was changed by something else and the history is cleared
+    pub fn undo(&mut self, text_left_of_cursor: &str) -> Option<(String, String)> {
+        let edit = self.edits.get(self.position.checked_sub(1)?)?.clone();
+        if !text_left_of_cursor.ends_with(&edit.inserted) {
+            info!(
+                "The text before the cursor no longer ends with '{}'. Unable to undo",
+                redact(&edit.inserted)
+            );
+            self.clear();
+            return None;
+        }
+        self.position -= 1;
+        self.sealed = true;
+        Some((edit.inserted, edit.erased))
+    }
+
+    /// Returns the text to erase before the cursor and the text to commit instead to redo the last edit that was undone
+    /// Returns 'None' if there is nothing to redo or if the text before the cursor no longer matches the edit. Then the text was changed by something else and the history is cleared
+    /// Undone edits can only be redone until something new is committed or erased
+    pub fn redo(&mut self, text_left_of_cursor: &str) -> Option<(String, String)> {
+        let edit = self.edits.get(self.position)?.clone();
+        if !text_left_of_cursor.ends_with(&edit.erased) {
+            info!(
+                "The text before the cursor no longer ends with '{}'. Unable to redo",
+                redact(&edit.erased)
+            );
+            self.clear();
+            return None;
+        }
+        self.position += 1;
+        self.sealed = true;
+        Some((edit.erased, edit.inserted))
+    }
+
+    /// Returns the last edit if the next commit or erasure is part of it
+    /// Edits that were undone can no longer be redone once something new is edited
+    fn get_open_edit(&mut self) -> Option<&mut Edit> {
+        let has_undone_edits = self.position < self.edits.len();
+        self.edits.truncate(self.position);
+        if self.sealed || has_undone_edits {
+            None
+        } else {
+            self.edits.last_mut()
+        }
+    }
+}
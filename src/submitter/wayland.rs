@@ -85,7 +85,7 @@ fn try_get_mgrs(
     if let Ok(vk_mgr) = global_mgr.instantiate_exact::<ZwpVirtualKeyboardManagerV1>(1) {
         virtual_keyboard_option = Some(vk_mgr);
     } else {
-        warn!("Your wayland compositor does not understand the wp_virtual_keyboard protocol. Entering any keycode will fail");
+        warn!("Your wayland compositor does not understand the wp_virtual_keyboard protocol. Keys that do not enter text (e.g. Esc, the arrow keys and shortcuts) will not work");
     }
     if let Ok(im_mgr) = global_mgr.instantiate_exact::<ZwpInputMethodManagerV2>(1) {
         input_method_mgr_option = Some(im_mgr);
@@ -15,6 +15,7 @@ use zwp_virtual_keyboard::virtual_keyboard_unstable_v1::zwp_virtual_keyboard_v1:
 
 // Imports from other modules
 use crate::keyboard;
+use crate::privacy::redact;
 
 // Macro to avoid repeating code
 // Unwraps the value or returns an error
@@ -218,6 +219,24 @@ impl VKService {
         }
     }
 
+    /// Try to press and then release the key while the modifiers are held
+    /// The modifiers that were pressed before are restored afterwards
+    pub fn press_release_key_with_modifiers(
+        &mut self,
+        keycode: u32,
+        modifiers: Vec<keyboard::Modifier>,
+    ) -> Result<(), SubmitError> {
+        let previously_pressed_modifiers = self.pressed_modifiers;
+        let mut new_modifier_state = previously_pressed_modifiers;
+        for modifier in modifiers {
+            new_modifier_state.insert(ModifiersBitflag::from(modifier));
+        }
+        unwrap_or_return!(self.send_modifiers_bitflag(new_modifier_state));
+        let result = self.press_release_key(keycode);
+        unwrap_or_return!(self.send_modifiers_bitflag(previously_pressed_modifiers));
+        result
+    }
+
     /// Try to toggle the key
     /// If it was pressed before, release it
     /// If it was released before, press it
@@ -301,13 +320,42 @@ impl VKService {
         }
     }
 
+    /// Returns true if a modifier is pressed that applications only apply to keys and not to text (e.g. CTRL)
+    pub fn has_command_modifiers(&self) -> bool {
+        self.pressed_modifiers
+            .intersects(ModifiersBitflag::CONTROL | ModifiersBitflag::MOD1 | ModifiersBitflag::MOD4)
+    }
+
+    /// Tries to submit the text by pressing and releasing the key of each character while the pressed modifiers stay pressed
+    /// This only works for characters that have a key in the keymap (letters, digits and the punctuation of an US keyboard). If any character does not, nothing is sent
+    pub fn send_str_as_keys(&mut self, text: &str) -> Result<(), SubmitError> {
+        let mut keys = Vec::new();
+        for character in text.chars() {
+            if let Some(key) = keycode_of_char(character) {
+                keys.push(key);
+            } else {
+                return Err(SubmitError::InvalidKeycode);
+            }
+        }
+        for (keycode, shift) in keys {
+            if shift {
+                unwrap_or_return!(
+                    self.press_release_key_with_modifiers(keycode, vec![keyboard::Modifier::Shift])
+                );
+            } else {
+                unwrap_or_return!(self.press_release_key(keycode));
+            }
+        }
+        Ok(())
+    }
+
     /// This method tries to submit a unicode string by entering each of its character individually with a combination of keypresses.
     /// There are multiple keypresses needed for each character and some applications do not support this!
     /// At least under GNOME this should work but it is very clumsy and should only be used as a last resort.
     pub fn send_unicode_str(&mut self, text: &str) -> Result<(), SubmitError> {
         warn!(
             "Trying to submit unicode string '{}' with virtual_keyboard protocol. Some applications do not support it. This is clumsy and should be avoided",
-            text
+            redact(text)
         );
 
         // Save state of the keys and modifiers
@@ -324,7 +372,7 @@ impl VKService {
                 Ok(()) => {}
                 Err(err) => {
                     result = Err(err);
-                    error!("Failed to submit the char '{}'", unicode_char);
+                    error!("Failed to submit the char '{}'", redact(unicode_char));
                     break;
                 }
             }
@@ -364,7 +412,10 @@ impl VKService {
             {
                 keycode
             } else {
-                error!("Keycode for '{}' was not found", hexadecimal_unicode_escape);
+                error!(
+                    "Keycode for '{}' was not found",
+                    redact(hexadecimal_unicode_escape)
+                );
                 return Err(SubmitError::InvalidKeycode);
             };
             unwrap_or_return!(self.press_release_key(*keycode));
@@ -395,3 +446,53 @@ impl VKService {
     .expect("Error setting Ctrl-C handler");
     }
 }
+
+/// Returns the keycode of the key that enters the character in the keymap and if SHIFT needs to be held for it
+fn keycode_of_char(character: char) -> Option<(u32, bool)> {
+    let (key_name, shift) = match character {
+        'a'..='z' | '0'..='9' => (character.to_ascii_uppercase().to_string(), false),
+        'A'..='Z' => (character.to_string(), true),
+        _ => {
+            let (key_name, shift) = match character {
+                ' ' => ("SPACE", false),
+                '-' => ("MINUS", false),
+                '_' => ("MINUS", true),
+                '=' => ("EQUAL", false),
+                '+' => ("EQUAL", true),
+                '[' => ("LEFTBRACE", false),
+                '{' => ("LEFTBRACE", true),
+                ']' => ("RIGHTBRACE", false),
+                '}' => ("RIGHTBRACE", true),
+                ';' => ("SEMICOLON", false),
+                ':' => ("SEMICOLON", true),
+                '\'' => ("APOSTROPHE", false),
+                '"' => ("APOSTROPHE", true),
+                '`' => ("GRAVE", false),
+                '~' => ("GRAVE", true),
+                '\\' => ("BACKSLASH", false),
+                '|' => ("BACKSLASH", true),
+                ',' => ("COMMA", false),
+                '<' => ("COMMA", true),
+                '.' => ("DOT", false),
+                '>' => ("DOT", true),
+                '/' => ("SLASH", false),
+                '?' => ("SLASH", true),
+                '!' => ("1", true),
+                '@' => ("2", true),
+                '#' => ("3", true),
+                '$' => ("4", true),
+                '%' => ("5", true),
+                '^' => ("6", true),
+                '&' => ("7", true),
+                '*' => ("8", true),
+                '(' => ("9", true),
+                ')' => ("0", true),
+                _ => return None,
+            };
+            (key_name.to_string(), shift)
+        }
+    };
+    input_event_codes_hashmap::KEY
+        .get::<str>(&key_name)
+        .map(|keycode| (*keycode, shift))
+}
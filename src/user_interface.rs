@@ -1,6 +1,7 @@
 // Imports from other crates
 #[cfg(feature = "suggestions")]
-use gtk::prelude::{ButtonExt, WidgetExt};
+use gtk::prelude::{AdjustmentExt, ContainerExt, ScrolledWindowExt};
+use gtk::prelude::{ButtonExt, PopoverExt, StyleContextExt, WidgetExt};
 use relm::Channel;
 use std::collections::HashMap;
 use wayland_protocols::unstable::text_input::v3::client::zwp_text_input_v3::{
@@ -10,14 +11,18 @@ use wayland_protocols::unstable::text_input::v3::client::zwp_text_input_v3::{
 // Imports from other modules
 #[cfg(feature = "gesture")]
 use crate::config::path_defaults;
-use crate::keyboard::TapMotion;
+use crate::keyboard::{Interaction, TapMotion};
+use crate::privacy::redact;
 
 // Modules
 mod gesture_handler;
 mod relm_update;
 mod relm_widget;
 mod ui_manager;
-use gesture_handler::{GestureModel, GestureSignal};
+use gesture_handler::{GestureModel, GestureSignal, POINTER_SEQUENCE};
+use relm_widget::GridBuilder;
+#[cfg(feature = "suggestions")]
+use relm_widget::{make_forget_word_gesture, make_suggestion_buttons, NO_SUGGESTION_BUTTONS};
 use ui_manager::UIManager;
 
 /// Saves all relevant information needed to display the user interface
@@ -31,8 +36,12 @@ pub enum Msg {
     // Contains the coordinates and the type of gesture signal. This message is sent when the user taps or swipes on the keyboard.
     // The raw signals need to be converted to an 'Interaction' before they can get sent to the keyboard module
     GestureSignal(f64, f64, GestureSignal),
+    // Contains the touch sequence, the coordinates and the type of a touch event. This message is sent when a finger touches the keyboard, moves or is lifted off it
+    Touch(usize, f64, f64, gdk::EventType),
     // Contains the layout, view and button_id to identify the button to eighter release or press. This is for the visual feedback only. The buttons do NOT do anything.
     ButtonInteraction(String, String, String, TapMotion),
+    // Contains the layout and the id of a button and if its key is latched. The button is shown as pressed in all views of the layout while the key is latched
+    LatchButton(String, String, bool),
     // Contains the id of the button which will open its popover
     OpenPopup(String),
     // Contains a string that will be submitted by the keyboard
@@ -40,8 +49,19 @@ pub enum Msg {
     // Updates the suggestions
     #[cfg(feature = "suggestions")]
     Suggestions(Vec<String>),
+    // Contains a word that will be removed from the user dictionary
+    #[cfg(feature = "suggestions")]
+    ForgetWord(String),
     // Contains the value the visibility of the user interface is supposed to be set to
     SetVisibility(bool),
+    // Check if the finger was held long enough for a long press. This message is sent when the delay for a long press of the pressed key passed
+    CheckLongPress,
+    // Repeat the key that is held down. This message is sent repeatedly while a key that repeats is held
+    RepeatKey,
+    // Execute the remaining steps of a sequence of key actions. This message is sent when the delay with the number passed
+    ContinueSequence(usize),
+    // Commit the character of a multi-tap key if no further tap followed. This message is sent when the timeout for multi-tap input passed
+    CheckMultiTap,
     // Contains the ContentHint and ContentPurpose the user_interface is supposed to be set to. This is not implemented yet but in the future, it could change the layout
     HintPurpose(ContentHint, ContentPurpose),
     // Contains the name of the layout and/or view the user interface should change to
@@ -71,7 +91,6 @@ pub enum Orientation {
 #[derive(Debug, Clone)]
 /// Contains the gesture handler to recognize a long press and a drag
 struct Gestures {
-    long_press_gesture: gtk::GestureLongPress,
     drag_gesture: gtk::GestureDrag,
 }
 
@@ -79,15 +98,26 @@ struct Gestures {
 #[derive(Debug, Clone)]
 /// Contains the buttons that display the suggestions
 struct Suggestions {
-    left: gtk::Button,
-    center: gtk::Button,
-    right: gtk::Button,
+    scrolled_window: gtk::ScrolledWindow,
+    suggestion_box: gtk::Box,
+    buttons: Vec<gtk::Button>,
+    _forget_word_gestures: Vec<gtk::GestureLongPress>, // Needs to be saved to prevent the gestures from getting dropped
+}
+
+/// A popover that was opened while the finger is still on the keyboard
+/// The highlighted entry follows the finger
+struct PopupSelection {
+    popover: gtk::Popover,
+    highlighted: Option<gtk::Button>,
+    finger_moved: bool,
 }
 
 /// Contains all widgets that need to get accessed
 struct Widgets {
     window: gtk::Window,
-    _overlay: gtk::Overlay,
+    overlay: gtk::Overlay,
+    key_preview: gtk::Label,
+    key_preview_labels: HashMap<(String, String, String), String>, // Labels of the keys that show a preview when pressed
     _draw_handler: relm::DrawHandler<gtk::DrawingArea>,
     #[cfg(feature = "suggestions")]
     suggestions: Suggestions,
@@ -102,6 +132,7 @@ pub struct Win {
     keyboard: crate::keyboard::Keyboard,
     widgets: Widgets,
     gestures: Gestures,
+    popup_selection: Option<PopupSelection>, // Only 'Some' while an entry of a popover is selected by sliding the finger
     ui_manager: UIManager,
     _channel: Channel<Msg>, // The channel is used to receive messages from other threads like the one the dbus_server is running in.
                             // It needs to be saved to prevent dropping it and thus closing the channel.
@@ -117,10 +148,127 @@ impl Win {
         // Calculate the relative coordinates
         let x_rel = x / width as f64;
         let y_rel = y / height as f64;
-        info!("The relative coordinate is x: {}, y: {}", x_rel, y_rel);
+        info!(
+            "The relative coordinate is x: {}, y: {}",
+            redact(x_rel),
+            redact(y_rel)
+        );
         (x_rel, y_rel)
     }
 
+    /// Show a magnified preview of the label of the pressed key above it
+    fn show_key_preview(&self, button: &gtk::ToggleButton, label: &str) {
+        let key_preview = &self.widgets.key_preview;
+        if let Some((x, y)) = button.translate_coordinates(&self.widgets.overlay, 0, 0) {
+            let allocation = button.allocation();
+            key_preview.set_text(label);
+            key_preview.set_size_request(allocation.width(), allocation.height());
+            key_preview.set_margin_start(x.max(0));
+            // The preview is shown on top of the key if there is no space above it
+            key_preview.set_margin_top((y - allocation.height()).max(0));
+            key_preview.show();
+        }
+    }
+
+    /// Open the popover under the finger. The finger can then slide to one of its entries
+    fn open_popup_under_finger(&mut self, button: &gtk::ToggleButton, popover: &gtk::Popover) {
+        // The preview of the key would cover the popover
+        self.widgets.key_preview.hide();
+        if let Some((x, y)) = self.model.gesture.get_finger_position() {
+            // The popover must not grab the input or the drag of the finger would be canceled
+            popover.set_modal(false);
+            if let Some((x, y)) = self
+                .widgets
+                .stack
+                .translate_coordinates(button, x as i32, y as i32)
+            {
+                popover.set_pointing_to(&gdk::Rectangle::new(x, y, 1, 1));
+            }
+            self.popup_selection = Some(PopupSelection {
+                popover: popover.clone(),
+                highlighted: None,
+                finger_moved: false,
+            });
+        } else {
+            popover.set_modal(true);
+            let allocation = button.allocation();
+            popover.set_pointing_to(&gdk::Rectangle::new(
+                0,
+                0,
+                allocation.width(),
+                allocation.height(),
+            ));
+        }
+        popover.show_all();
+    }
+
+    /// Cancel the interaction that is in progress without submitting anything
+    /// The gesture path, the preview and a popover under the finger are cleared and the keyboard releases the pressed keys
+    fn cancel_interaction(&mut self) {
+        self.model.gesture.reset();
+        self.widgets.key_preview.hide();
+        if let Some(popup_selection) = self.popup_selection.take() {
+            if let Some(highlighted) = &popup_selection.highlighted {
+                highlighted.style_context().remove_class("highlighted");
+            }
+            popup_selection.popover.hide();
+        }
+        #[cfg(feature = "gesture")]
+        self.draw_path();
+        self.keyboard.input(0.0, 0.0, Interaction::Cancel);
+    }
+
+    /// Highlight the entry of the popover under the finger
+    /// When the finger is lifted, the highlighted entry is entered. If the finger was lifted outside of the popover, nothing is entered
+    /// If the finger was lifted without moving, the popover stays open so an entry can be tapped
+    fn update_popup_selection(&mut self, x: f64, y: f64, finger_lifted: bool) {
+        let mut popup_selection = if let Some(popup_selection) = self.popup_selection.take() {
+            popup_selection
+        } else {
+            return;
+        };
+        if !finger_lifted {
+            popup_selection.finger_moved = true;
+        }
+        let button_under_finger = GridBuilder::get_popover_buttons(&popup_selection.popover)
+            .into_iter()
+            .find(|button| {
+                self.widgets
+                    .stack
+                    .translate_coordinates(button, x as i32, y as i32)
+                    .map_or(false, |(x, y)| {
+                        let allocation = button.allocation();
+                        x >= 0 && y >= 0 && x < allocation.width() && y < allocation.height()
+                    })
+            });
+        if popup_selection.highlighted != button_under_finger {
+            if let Some(highlighted) = &popup_selection.highlighted {
+                highlighted.style_context().remove_class("highlighted");
+            }
+            if let Some(button_under_finger) = &button_under_finger {
+                button_under_finger.style_context().add_class("highlighted");
+            }
+            popup_selection.highlighted = button_under_finger;
+        }
+        if !finger_lifted {
+            self.popup_selection = Some(popup_selection);
+            return;
+        }
+        if let Some(highlighted) = &popup_selection.highlighted {
+            highlighted.style_context().remove_class("highlighted");
+            if let Some(label) = highlighted.label() {
+                self.keyboard.submit_text(label.to_string(), false);
+            }
+            popup_selection.popover.hide();
+        } else if popup_selection.finger_moved {
+            info!("Finger was lifted outside of the popup. Nothing is entered");
+            popup_selection.popover.hide();
+        } else {
+            // The popover needs to grab the input again to close when the user taps outside of it
+            popup_selection.popover.set_modal(true);
+        }
+    }
+
     #[cfg(feature = "gesture")]
     /// Erases the path/gesture the user drew on the user interface
     fn erase_path(&mut self) {
@@ -171,21 +319,31 @@ impl Win {
     }
 
     #[cfg(feature = "suggestions")]
+    /// Show the suggestions on the suggestion buttons
+    /// Buttons are added if there are more suggestions than buttons. The ones that are not needed are hidden, but the first few are always shown
     fn update_suggestions(&mut self, suggestions: Vec<String>) {
-        if let Some(left) = suggestions.get(0) {
-            self.widgets.suggestions.left.set_label(left);
-        } else {
-            self.widgets.suggestions.left.set_label("");
-        }
-        if let Some(center) = suggestions.get(1) {
-            self.widgets.suggestions.center.set_label(center);
-        } else {
-            self.widgets.suggestions.center.set_label("");
+        let suggestion_widgets = &mut self.widgets.suggestions;
+        if suggestions.len() > suggestion_widgets.buttons.len() {
+            let new_buttons = make_suggestion_buttons(
+                &self.relm,
+                suggestions.len() - suggestion_widgets.buttons.len(),
+            );
+            for button in new_buttons {
+                suggestion_widgets.suggestion_box.add(&button);
+                suggestion_widgets
+                    ._forget_word_gestures
+                    .push(make_forget_word_gesture(&self.relm, &button));
+                suggestion_widgets.buttons.push(button);
+            }
         }
-        if let Some(right) = suggestions.get(2) {
-            self.widgets.suggestions.right.set_label(right);
-        } else {
-            self.widgets.suggestions.right.set_label("");
+        for (index, button) in suggestion_widgets.buttons.iter().enumerate() {
+            button.set_label(suggestions.get(index).map_or("", String::as_str));
+            button.set_visible(index < NO_SUGGESTION_BUTTONS.max(suggestions.len()));
         }
+        // The first suggestion is shown again
+        suggestion_widgets
+            .scrolled_window
+            .hadjustment()
+            .set_value(0.0);
     }
 }
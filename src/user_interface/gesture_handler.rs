@@ -1,7 +1,8 @@
 // Imports from other crates
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 // Imports from other modules
+use crate::config::input_settings::{DOUBLE_TAP_DELAY, DOUBLE_TAP_MAX_DISTANCE, LONG_PRESS_DELAY};
 use crate::keyboard::{Interaction, SwipeAction, TapDuration, TapMotion};
 
 /// Coordinate and time of an interaction
@@ -12,13 +13,16 @@ pub struct Point {
     pub time: Instant,
 }
 
+/// Touch sequence used for pointers without touch events (e.g. a mouse)
+pub const POINTER_SEQUENCE: usize = 0;
+
 /// Signals that can be sent to the GestureModel
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum GestureSignal {
     DragBegin,
     DragUpdate,
     DragEnd,
-    LongPress,
+    Cancel,
 }
 
 /// Internal type needed to make the interpretation of the signals simpler
@@ -32,9 +36,17 @@ enum GestureInterpretation {
 #[derive(Clone)]
 /// Converts the signals of user input into 'Interaction's the keyboard can handle
 /// and stores the points of the swipe path
+/// The times of the signals are used to detect long presses and double taps
 pub struct GestureModel {
     prev_interpretation: GestureInterpretation,
     swipe_path: Vec<Point>,
+    no_touches: usize, // Number of fingers currently touching the keyboard
+    finger_position: Option<(f64, f64)>, // Only 'Some' while the finger is on the keyboard
+    touchscreen_used: bool, // True once the first touch event was received
+    press_time: Option<Instant>, // Only 'Some' while the finger is on the keyboard
+    long_press_delay: Duration, // Time the finger needs to be held for a long press of the pressed key
+    last_tap: Option<Point>,    // Where and when the last short tap ended
+    second_tap: bool, // True if the finger touched the keyboard shortly after and close to the last tap
 }
 
 impl GestureModel {
@@ -45,34 +57,112 @@ impl GestureModel {
         GestureModel {
             prev_interpretation,
             swipe_path,
+            no_touches: 0,
+            finger_position: None,
+            touchscreen_used: false,
+            press_time: None,
+            long_press_delay: Duration::from_millis(LONG_PRESS_DELAY),
+            last_tap: None,
+            second_tap: false,
+        }
+    }
+
+    /// Set the time the finger needs to be held for a long press of the pressed key
+    pub fn set_long_press_delay(&mut self, long_press_delay: Duration) {
+        self.long_press_delay = long_press_delay;
+    }
+
+    /// Returns a long press if the finger was held without moving for longer than the long press delay at the time
+    /// A long press is only returned once for each press
+    pub fn check_long_press(&mut self, time: Instant) -> Option<((f64, f64), Interaction)> {
+        let press_time = self.press_time?;
+        if self.prev_interpretation != GestureInterpretation::NoGesture
+            || time.saturating_duration_since(press_time) < self.long_press_delay
+        {
+            return None;
+        }
+        self.prev_interpretation = GestureInterpretation::LongPress;
+        self.finger_position.map(|position| {
+            (
+                position,
+                Interaction::Tap(TapDuration::Long, TapMotion::Press),
+            )
+        })
+    }
+
+    /// Returns true if the keyboard received touch events before
+    /// If it did not, the keyboard is used with a pointer (e.g. a mouse)
+    pub fn is_touchscreen_used(&self) -> bool {
+        self.touchscreen_used
+    }
+
+    /// Returns true if a swipe is in progress
+    pub fn is_swiping(&self) -> bool {
+        self.prev_interpretation == GestureInterpretation::Swipe
+    }
+
+    /// Returns the position of the finger if it is currently on the keyboard
+    pub fn get_finger_position(&self) -> Option<(f64, f64)> {
+        self.finger_position
+    }
+
+    /// Update the number of fingers touching the keyboard with the type of a touch event and return the new number
+    pub fn update_touches(&mut self, event_type: gdk::EventType) -> usize {
+        match event_type {
+            gdk::EventType::TouchBegin => {
+                self.no_touches += 1;
+                self.touchscreen_used = true;
+            }
+            gdk::EventType::TouchEnd | gdk::EventType::TouchCancel => {
+                self.no_touches = self.no_touches.saturating_sub(1)
+            }
+            _ => {}
         }
+        self.no_touches
+    }
+
+    /// Forget the current gesture
+    /// The following signals are interpreted as if the finger had just touched the keyboard
+    pub fn reset(&mut self) {
+        self.prev_interpretation = GestureInterpretation::NoGesture;
+        self.swipe_path = Vec::new();
+        self.finger_position = None;
+        self.press_time = None;
+        self.last_tap = None;
+        self.second_tap = false;
     }
 
-    /// Converts the signal to an Interaction
+    /// Converts the signal that was received at the time to an Interaction
     pub fn convert_to_interaction(
         &mut self,
         x: f64,
         y: f64,
         input: GestureSignal,
+        time: Instant,
     ) -> ((f64, f64), Interaction) {
+        self.finger_position = if matches!(input, GestureSignal::DragEnd | GestureSignal::Cancel) {
+            None
+        } else {
+            Some((x, y))
+        };
         let interaction = match input {
             // If the signal was a DragBegin, the interaction was a short press
-            GestureSignal::DragBegin => Interaction::Tap(TapDuration::Short, TapMotion::Press),
-            // If the signal was a LongPress, the interaction was a long press
-            GestureSignal::LongPress => {
-                self.prev_interpretation = GestureInterpretation::LongPress;
-                Interaction::Tap(TapDuration::Long, TapMotion::Press)
+            // It is remembered if the press followed the last tap closely enough to be the second tap of a double tap
+            GestureSignal::DragBegin => {
+                self.press_time = Some(time);
+                self.second_tap = self.last_tap.take().map_or(false, |last_tap| {
+                    time.saturating_duration_since(last_tap.time)
+                        <= Duration::from_millis(DOUBLE_TAP_DELAY)
+                        && (x - last_tap.x).hypot(y - last_tap.y) <= DOUBLE_TAP_MAX_DISTANCE
+                });
+                Interaction::Tap(TapDuration::Short, TapMotion::Press)
             }
             // If the signal was a DragUpdate, the interaction depends on the previous interpretation of a signal
             // If the previous interpretation was a swipe already, the interaction is a SwipeUpdate
             // Otherwise it is the beginning of a swipe
             // In all cases it adds the coordinates to the swipe path
             GestureSignal::DragUpdate => {
-                self.swipe_path.push(Point {
-                    x,
-                    y,
-                    time: Instant::now(),
-                });
+                self.swipe_path.push(Point { x, y, time });
                 match self.prev_interpretation {
                     GestureInterpretation::NoGesture | GestureInterpretation::LongPress => {
                         self.prev_interpretation = GestureInterpretation::Swipe;
@@ -86,9 +176,13 @@ impl GestureModel {
             GestureSignal::DragEnd => {
                 // Clear swipe path
                 self.swipe_path = Vec::new();
+                self.press_time = None;
                 let new_interaction = match self.prev_interpretation {
-                    // If no gesture was detected, it was a short release
+                    // If no gesture was detected, it was the second tap of a double tap..
+                    GestureInterpretation::NoGesture if self.second_tap => Interaction::DoubleTap,
+                    // .. or a short release. The next tap might be the second tap of a double tap
                     GestureInterpretation::NoGesture => {
+                        self.last_tap = Some(Point { x, y, time });
                         Interaction::Tap(TapDuration::Short, TapMotion::Release)
                     }
                     // If a long press was previously detected, it was a long release
@@ -99,8 +193,14 @@ impl GestureModel {
                     GestureInterpretation::Swipe => Interaction::Swipe(SwipeAction::Finish),
                 };
                 self.prev_interpretation = GestureInterpretation::NoGesture;
+                self.second_tap = false;
                 new_interaction
             }
+            // If the signal was a Cancel, the gesture is forgotten
+            GestureSignal::Cancel => {
+                self.reset();
+                Interaction::Cancel
+            }
         };
         ((x, y), interaction)
     }
@@ -111,3 +211,176 @@ impl GestureModel {
         self.swipe_path.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the interaction for the signal that was received the milliseconds after the start
+    fn convert(
+        gesture_model: &mut GestureModel,
+        start: Instant,
+        millis: u64,
+        x: f64,
+        input: GestureSignal,
+    ) -> Interaction {
+        let time = start + Duration::from_millis(millis);
+        gesture_model.convert_to_interaction(x, 0.0, input, time).1
+    }
+
+    #[test]
+    fn short_tap() {
+        let mut gesture_model = GestureModel::new();
+        let start = Instant::now();
+        assert_eq!(
+            convert(&mut gesture_model, start, 0, 0.0, GestureSignal::DragBegin),
+            Interaction::Tap(TapDuration::Short, TapMotion::Press)
+        );
+        assert_eq!(
+            gesture_model.check_long_press(start + Duration::from_millis(100)),
+            None
+        );
+        assert_eq!(
+            convert(&mut gesture_model, start, 100, 0.0, GestureSignal::DragEnd),
+            Interaction::Tap(TapDuration::Short, TapMotion::Release)
+        );
+    }
+
+    #[test]
+    fn long_press_after_delay() {
+        let mut gesture_model = GestureModel::new();
+        let start = Instant::now();
+        convert(&mut gesture_model, start, 0, 0.0, GestureSignal::DragBegin);
+        let long_press_time = start + Duration::from_millis(LONG_PRESS_DELAY);
+        assert_eq!(
+            gesture_model.check_long_press(long_press_time),
+            Some((
+                (0.0, 0.0),
+                Interaction::Tap(TapDuration::Long, TapMotion::Press)
+            ))
+        );
+        // A long press is only detected once
+        assert_eq!(gesture_model.check_long_press(long_press_time), None);
+        assert_eq!(
+            convert(
+                &mut gesture_model,
+                start,
+                LONG_PRESS_DELAY + 100,
+                0.0,
+                GestureSignal::DragEnd
+            ),
+            Interaction::Tap(TapDuration::Long, TapMotion::Release)
+        );
+    }
+
+    #[test]
+    fn long_press_delay_of_key() {
+        let mut gesture_model = GestureModel::new();
+        let start = Instant::now();
+        convert(&mut gesture_model, start, 0, 0.0, GestureSignal::DragBegin);
+        gesture_model.set_long_press_delay(Duration::from_millis(1000));
+        assert_eq!(
+            gesture_model.check_long_press(start + Duration::from_millis(LONG_PRESS_DELAY)),
+            None
+        );
+        assert!(gesture_model
+            .check_long_press(start + Duration::from_millis(1000))
+            .is_some());
+    }
+
+    #[test]
+    fn no_long_press_while_swiping() {
+        let mut gesture_model = GestureModel::new();
+        let start = Instant::now();
+        convert(&mut gesture_model, start, 0, 0.0, GestureSignal::DragBegin);
+        assert_eq!(
+            convert(
+                &mut gesture_model,
+                start,
+                50,
+                50.0,
+                GestureSignal::DragUpdate
+            ),
+            Interaction::Swipe(SwipeAction::Begin)
+        );
+        assert_eq!(
+            gesture_model.check_long_press(start + Duration::from_millis(LONG_PRESS_DELAY)),
+            None
+        );
+    }
+
+    #[test]
+    fn double_tap() {
+        let mut gesture_model = GestureModel::new();
+        let start = Instant::now();
+        convert(&mut gesture_model, start, 0, 0.0, GestureSignal::DragBegin);
+        convert(&mut gesture_model, start, 50, 0.0, GestureSignal::DragEnd);
+        convert(
+            &mut gesture_model,
+            start,
+            150,
+            5.0,
+            GestureSignal::DragBegin,
+        );
+        assert_eq!(
+            convert(&mut gesture_model, start, 200, 5.0, GestureSignal::DragEnd),
+            Interaction::DoubleTap
+        );
+        // A third tap starts a new double tap
+        convert(
+            &mut gesture_model,
+            start,
+            250,
+            5.0,
+            GestureSignal::DragBegin,
+        );
+        assert_eq!(
+            convert(&mut gesture_model, start, 300, 5.0, GestureSignal::DragEnd),
+            Interaction::Tap(TapDuration::Short, TapMotion::Release)
+        );
+    }
+
+    #[test]
+    fn no_double_tap_if_too_late_or_too_far() {
+        let mut gesture_model = GestureModel::new();
+        let start = Instant::now();
+        convert(&mut gesture_model, start, 0, 0.0, GestureSignal::DragBegin);
+        convert(&mut gesture_model, start, 50, 0.0, GestureSignal::DragEnd);
+        let late = 50 + DOUBLE_TAP_DELAY + 1;
+        convert(
+            &mut gesture_model,
+            start,
+            late,
+            0.0,
+            GestureSignal::DragBegin,
+        );
+        assert_eq!(
+            convert(
+                &mut gesture_model,
+                start,
+                late + 50,
+                0.0,
+                GestureSignal::DragEnd
+            ),
+            Interaction::Tap(TapDuration::Short, TapMotion::Release)
+        );
+        let far = DOUBLE_TAP_MAX_DISTANCE + 1.0;
+        convert(
+            &mut gesture_model,
+            start,
+            late + 100,
+            far,
+            GestureSignal::DragBegin,
+        );
+        assert_eq!(
+            convert(
+                &mut gesture_model,
+                start,
+                late + 150,
+                far,
+                GestureSignal::DragEnd
+            ),
+            Interaction::Tap(TapDuration::Short, TapMotion::Release)
+        );
+    }
+}
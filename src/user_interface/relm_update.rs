@@ -1,8 +1,12 @@
 // Imports from other crates
 use gtk::prelude::{ToggleButtonExt, WidgetExt};
+use std::time::{Duration, Instant};
 
 // Imports from other modules
-use super::{GestureModel, Model, Msg, TapMotion, Win};
+use super::{GestureModel, GestureSignal, Model, Msg, TapMotion, Win, POINTER_SEQUENCE};
+use crate::config::input_settings::KEY_REPEAT_INTERVAL;
+use crate::keyboard::{Interaction, TapDuration, TouchMotion};
+use crate::privacy::redact;
 
 impl relm::Update for Win {
     // Specify the model used for this widget.
@@ -30,35 +34,145 @@ impl relm::Update for Win {
             // The GestureModel converts  'GestureSignal's to 'Interaction's the keyboard can understand. The coordinates are converted to relative coordinates
             // and the new values are sent to the keyboard as input
             Msg::GestureSignal(x, y, gesture_signal) => {
+                // Layouts in direct mode are controlled by the touches of each finger. Pointers without touch events (e.g. a mouse) act like a single finger
+                if self.keyboard.is_direct() {
+                    if !self.model.gesture.is_touchscreen_used() {
+                        let touch_motion = match gesture_signal {
+                            GestureSignal::DragBegin => TouchMotion::Begin,
+                            GestureSignal::DragUpdate => TouchMotion::Update,
+                            GestureSignal::DragEnd => TouchMotion::End,
+                            GestureSignal::Cancel => TouchMotion::Cancel,
+                        };
+                        let (x, y) = self.get_rel_coordinates(x, y);
+                        self.keyboard
+                            .input_touch(POINTER_SEQUENCE, x, y, touch_motion);
+                    }
+                    return;
+                }
+                // While several fingers type at once, the keyboard commits the key of each touch itself. Drags are not interpreted as swipes
+                if self.keyboard.is_rolling_over() {
+                    self.model.gesture.reset();
+                    return;
+                }
                 let ((x, y), interaction) =
                     self.model
                         .gesture
-                        .convert_to_interaction(x, y, gesture_signal);
+                        .convert_to_interaction(x, y, gesture_signal, Instant::now());
+                // An interrupted drag releases the pressed keys without submitting anything
+                if interaction == Interaction::Cancel {
+                    self.cancel_interaction();
+                    return;
+                }
+                // While a popover is open under the finger, the finger selects one of its entries. The keyboard does not get the interaction
+                if self.popup_selection.is_some() {
+                    self.update_popup_selection(x, y, gesture_signal == GestureSignal::DragEnd);
+                    return;
+                }
                 let (x, y) = self.get_rel_coordinates(x, y);
                 self.keyboard.input(x, y, interaction);
+                // The delay for a long press depends on the pressed key
+                if interaction == Interaction::Tap(TapDuration::Short, TapMotion::Press) {
+                    let long_press_delay = self.keyboard.get_long_press_delay();
+                    self.model
+                        .gesture
+                        .set_long_press_delay(Duration::from_millis(long_press_delay));
+                    relm::timeout(
+                        self.relm.stream(),
+                        long_press_delay.try_into().unwrap_or(u32::MAX),
+                        || Msg::CheckLongPress,
+                    );
+                }
+            }
+            // The finger might have been held long enough for a long press
+            Msg::CheckLongPress => {
+                if let Some(((x, y), interaction)) =
+                    self.model.gesture.check_long_press(Instant::now())
+                {
+                    let (x, y) = self.get_rel_coordinates(x, y);
+                    self.keyboard.input(x, y, interaction);
+                    if self.keyboard.is_repeating_key() {
+                        relm::timeout(
+                            self.relm.stream(),
+                            KEY_REPEAT_INTERVAL.try_into().unwrap_or(u32::MAX),
+                            || Msg::RepeatKey,
+                        );
+                    }
+                }
+            }
+            // The key is repeated until it is no longer held
+            Msg::RepeatKey => {
+                if self.keyboard.repeat_key() {
+                    relm::timeout(
+                        self.relm.stream(),
+                        KEY_REPEAT_INTERVAL.try_into().unwrap_or(u32::MAX),
+                        || Msg::RepeatKey,
+                    );
+                }
+            }
+            // The delay of a sequence of key actions passed
+            Msg::ContinueSequence(delay_number) => {
+                self.keyboard.continue_sequence(delay_number);
+            }
+            // The character of a multi-tap key might not have been changed for long enough to commit it
+            Msg::CheckMultiTap => {
+                self.keyboard.check_multi_tap();
+            }
+            // The GestureModel keeps track of the number of fingers touching the keyboard. The keyboard needs to know if there is more than one
+            // Each touch is also sent to the keyboard because layouts in direct mode are controlled by the touches of each finger
+            Msg::Touch(sequence, x, y, event_type) => {
+                let no_touches = self.model.gesture.update_touches(event_type);
+                self.keyboard.set_multiple_touches(no_touches > 1);
+                let touch_motion = match event_type {
+                    gdk::EventType::TouchBegin => Some(TouchMotion::Begin),
+                    gdk::EventType::TouchUpdate => Some(TouchMotion::Update),
+                    gdk::EventType::TouchEnd => Some(TouchMotion::End),
+                    gdk::EventType::TouchCancel => Some(TouchMotion::Cancel),
+                    _ => None,
+                };
+                if let Some(touch_motion) = touch_motion {
+                    let (x, y) = self.get_rel_coordinates(x, y);
+                    self.keyboard.input_touch(sequence, x, y, touch_motion);
+                }
             }
             // If a button was clicked, activate it or deactivate it and give haptic feedback
             Msg::ButtonInteraction(layout, view, key_id, tap_motion) => {
                 info! {
-                    "Trying to interact with '{}' key", key_id
+                    "Trying to interact with '{}' key", redact(&key_id)
                 };
-                if let Some((button, _)) = self.widgets.buttons.get(&(layout, view, key_id)) {
+                let button_id = (layout, view, key_id);
+                if let Some((button, _)) = self.widgets.buttons.get(&button_id) {
                     // Activate/Deactivate it (visual feedback of the button press)
                     button.set_active(tap_motion == TapMotion::Press);
                     // Give haptic feedback
                     self.ui_manager
                         .haptic_feedback(tap_motion == TapMotion::Press);
+                    // Show the preview of the key while it is pressed. No preview is shown during a gesture
+                    self.widgets.key_preview.hide();
+                    if tap_motion == TapMotion::Press && !self.model.gesture.is_swiping() {
+                        if let Some(label) = self.widgets.key_preview_labels.get(&button_id) {
+                            self.show_key_preview(button, label);
+                        }
+                    }
                 } else {
                     error!("UI does not know the key id and can't handle the ButtonInteraction");
                 }
             }
+            // Show the latched state of the key in all views of the layout, so it stays visible when the view is switched
+            Msg::LatchButton(layout, key_id, latched) => {
+                for ((button_layout, _, button_key_id), (button, _)) in &self.widgets.buttons {
+                    if *button_layout == layout && *button_key_id == key_id {
+                        button.set_active(latched);
+                    }
+                }
+            }
             // Open the popover of the specified button
             Msg::OpenPopup(key_id) => {
                 let (layout, view) = self.ui_manager.current_layout_view.clone();
                 if let Some((button, popover)) = self.widgets.buttons.get(&(layout, view, key_id)) {
                     button.set_active(false);
                     if let Some(popover) = popover {
-                        popover.show_all();
+                        let (button, popover) = (button.clone(), popover.clone());
+                        self.open_popup_under_finger(&button, &popover);
                     } else {
                         error!("The button does not have a popup to open");
                     }
@@ -73,13 +187,27 @@ impl relm::Update for Win {
             Msg::Suggestions(suggestions) => {
                 self.update_suggestions(suggestions);
             }
+            // Tell the keyboard to remove the word from the user dictionary
+            #[cfg(feature = "suggestions")]
+            Msg::ForgetWord(word) => self.keyboard.forget_word(&word),
+            // A touch can not continue when the keyboard is hidden
             Msg::SetVisibility(new_visibility) => {
+                if !new_visibility {
+                    self.cancel_interaction();
+                    // The input method is already deactivated, so text that is still composed can no longer be committed
+                    self.keyboard.cancel_preedit();
+                }
+                // The keyboard is shown and hidden when the focus changes. The edits in the previous text field can no longer be undone
+                self.keyboard.clear_history();
                 self.ui_manager.change_visibility(new_visibility);
             }
-            // Have the UIManager handle the change of hint/purpose
-            Msg::HintPurpose(content_hint, content_purpose) => self
-                .ui_manager
-                .change_hint_purpose(content_hint, content_purpose),
+            // Have the UIManager and the keyboard handle the change of hint/purpose
+            Msg::HintPurpose(content_hint, content_purpose) => {
+                self.keyboard
+                    .change_hint_purpose(content_hint, content_purpose);
+                self.ui_manager
+                    .change_hint_purpose(content_hint, content_purpose);
+            }
             // Have the UIManager handle the change of the layout/view
             Msg::ChangeUILayoutView(layout, view) => {
                 let _ = self.ui_manager.change_layout_view(&layout, view); // Result not relevant
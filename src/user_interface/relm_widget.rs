@@ -1,23 +1,22 @@
 // Imports from other crates
 use gtk::prelude::{
-    ContainerExt, CssProviderExt, GestureDragExt, GtkWindowExt, OverlayExt, StackExt, WidgetExt,
+    ContainerExt, CssProviderExt, GestureDragExt, GtkWindowExt, OverlayExt, StackExt,
+    StyleContextExt, WidgetExt,
 };
 use relm::Channel;
+use std::collections::HashMap;
 
 #[cfg(feature = "suggestions")]
-use gtk::prelude::{ButtonExt, StyleContextExt};
-#[cfg(feature = "suggestions")]
-use std::collections::HashMap;
+use gtk::prelude::{ButtonExt, EventControllerExt, GestureExt};
 
 // Imports from other modules
-use super::gesture_handler::GestureSignal;
+use super::gesture_handler::{GestureSignal, POINTER_SEQUENCE};
 #[cfg(feature = "suggestions")]
 use super::Suggestions;
 use super::{Gestures, Msg, Orientation, UIManager, Widgets, Win};
 use crate::config::directories;
-use crate::config::input_settings;
 use crate::submitter::wayland;
-use crate::{keyboard, keyboard::UIConnector};
+use crate::{keyboard, keyboard::KeyDisplay, keyboard::UIConnector};
 
 // Modules
 mod grid_builder;
@@ -26,6 +25,8 @@ mod grid_builder;
 pub use grid_builder::GridBuilder;
 
 pub const WINDOW_DEFAULT_HEIGHT: i32 = 720;
+#[cfg(feature = "suggestions")]
+pub const NO_SUGGESTION_BUTTONS: usize = 3; // Number of suggestion buttons that are always shown. More are added when needed
 
 /// Used to build the UI
 impl relm::Widget for Win {
@@ -49,16 +50,43 @@ impl relm::Widget for Win {
         let layout_meta = keyboard::LayoutMeta::deserialize();
         // Build the keyboard struct that stores all logic of the keys
         let keyboard = keyboard::Keyboard::from(message_pipe, &layout_meta);
+        // Remember the labels of the keys that show a preview, before the meta data is used up to build the grids
+        let key_preview_labels = get_key_preview_labels(&layout_meta);
         // Build the stack of grids of the layouts from the meta data
         let (stack, buttons) = GridBuilder::make_stack(relm, layout_meta);
         // Make a new drawing area on which the gesture paths will get painted to
         let drawing_area = gtk::DrawingArea::new();
         let mut draw_handler = relm::DrawHandler::new().expect("draw handler");
         draw_handler.init(&drawing_area);
+        // Send a 'Touch' message to the UI whenever a finger touches the drawing area, moves or is lifted off it
+        // Each finger is identified by its touch sequence
+        drawing_area.add_events(gdk::EventMask::TOUCH_MASK);
+        relm::connect!(
+            relm,
+            drawing_area,
+            connect_touch_event(_, event),
+            return (
+                {
+                    let (x, y) = event.coords().unwrap_or((-1000.0, -1000.0));
+                    let sequence = event
+                        .event_sequence()
+                        .map_or(POINTER_SEQUENCE, |sequence| sequence.as_ptr() as usize);
+                    Some(Msg::Touch(sequence, x, y, event.event_type()))
+                },
+                gtk::Inhibit(false)
+            )
+        );
         // Overlay the drawing area over the stack of layouts
         let overlay = gtk::Overlay::new();
         overlay.add(&stack);
         overlay.add_overlay(&drawing_area);
+        // Add the preview of pressed keys on top. It must not catch any input
+        let key_preview = gtk::Label::new(None);
+        key_preview.style_context().add_class("key_preview");
+        key_preview.set_halign(gtk::Align::Start);
+        key_preview.set_valign(gtk::Align::Start);
+        overlay.add_overlay(&key_preview);
+        overlay.set_overlay_pass_through(&key_preview, true);
 
         // Make the vertical box that stores the overlay and the box of suggestions
         let v_box = gtk::Box::new(gtk::Orientation::Vertical, 2);
@@ -71,15 +99,31 @@ impl relm::Widget for Win {
             let h_box = gtk::Box::new(gtk::Orientation::Horizontal, 0);
             h_box.set_margin_start(0);
             h_box.set_margin_end(0);
-            let suggestions_and_pref_buttons = make_suggestions_and_pref_buttons(relm, &keyboard);
+            let (suggestion_buttons, preferences_button) =
+                make_suggestions_and_pref_buttons(relm, &keyboard);
+            let forget_word_gestures = suggestion_buttons
+                .iter()
+                .map(|button| make_forget_word_gesture(relm, button))
+                .collect();
+            // The suggestions can be scrolled sideways if there are more than fit (e.g. the candidates for pinyin)
+            let suggestion_box = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+            for button in &suggestion_buttons {
+                suggestion_box.add(button);
+            }
+            let scrolled_window = gtk::ScrolledWindow::builder()
+                .hscrollbar_policy(gtk::PolicyType::Automatic)
+                .vscrollbar_policy(gtk::PolicyType::Never)
+                .hexpand(true)
+                .build();
+            scrolled_window.add(&suggestion_box);
+            h_box.add(&scrolled_window);
+            h_box.add(&preferences_button);
             suggestions = Suggestions {
-                left: suggestions_and_pref_buttons.get(0).unwrap().clone(),
-                center: suggestions_and_pref_buttons.get(1).unwrap().clone(),
-                right: suggestions_and_pref_buttons.get(2).unwrap().clone(),
+                scrolled_window,
+                suggestion_box,
+                buttons: suggestion_buttons,
+                _forget_word_gestures: forget_word_gestures,
             };
-            for button in suggestions_and_pref_buttons {
-                h_box.add(&button);
-            }
             v_box.add(&h_box);
             info! {"Suggestion buttons added"};
         }
@@ -90,9 +134,7 @@ impl relm::Widget for Win {
         window.set_default_height(WINDOW_DEFAULT_HEIGHT);
         window.add(&v_box);
 
-        // Add a GestureLongPress handler to the drawing area
-        let long_press_gesture = gtk::GestureLongPress::new(&drawing_area);
-        long_press_gesture.set_delay_factor(input_settings::LONG_PRESS_DELAY_FACTOR);
+        // Add a GestureDrag handler to the drawing area. Long presses and double taps are detected by the GestureModel
         let drag_gesture = gtk::GestureDrag::new(&drawing_area);
 
         // Create a channel to be able to send a message from another thread.
@@ -119,17 +161,17 @@ impl relm::Widget for Win {
             keyboard,
             widgets: Widgets {
                 window,
-                _overlay: overlay,
+                overlay,
+                key_preview,
+                key_preview_labels,
                 _draw_handler: draw_handler,
                 #[cfg(feature = "suggestions")]
                 suggestions,
                 stack,
                 buttons,
             },
-            gestures: Gestures {
-                long_press_gesture,
-                drag_gesture,
-            },
+            gestures: Gestures { drag_gesture },
+            popup_selection: None,
             ui_manager,
             _channel: channel,
         }
@@ -160,14 +202,6 @@ impl relm::Widget for Win {
                 false
             });
 
-        // Send a 'GestureSignal' message to the UI with the coordinates and a GestureSignal::LongPress variant when there was a long press on the overlay
-        relm::connect!(
-            self.gestures.long_press_gesture,
-            connect_pressed(_, x, y), // Long press detected
-            self.relm,
-            Msg::GestureSignal(x, y, GestureSignal::LongPress)
-        );
-
         // Send a 'GestureSignal' message to the UI with the coordinates and a GestureSignal::DragBegin variant when the beginning of a drag was detected on the overlay
         relm::connect!(
             self.gestures.drag_gesture,
@@ -204,6 +238,17 @@ impl relm::Widget for Win {
             }
         );
 
+        // Send a 'GestureSignal' message to the UI with a GestureSignal::Cancel variant when the drag was interrupted (e.g. by the compositor)
+        relm::connect!(
+            self.gestures.drag_gesture,
+            connect_cancel(drag_gesture, _),
+            self.relm,
+            {
+                let (x, y) = drag_gesture.start_point().unwrap_or((-1000.0, -1000.0));
+                Msg::GestureSignal(x, y, GestureSignal::Cancel)
+            }
+        );
+
         // Connect the signal `delete_event` to send the `Quit` message.
         relm::connect!(
             self.relm,
@@ -216,12 +261,13 @@ impl relm::Widget for Win {
         #[cfg(feature = "gesture")]
         relm::connect!(
             self.relm,
-            self.widgets._overlay,
+            self.widgets.overlay,
             connect_draw(_, _),
             return (Some(Msg::UpdateDrawBuffer), gtk::Inhibit(false))
         );
 
         self.widgets.window.show_all(); // All widgets are visible
+        self.widgets.key_preview.hide(); // The preview is only shown while a key is pressed
         self.widgets.window.hide(); // Keyboard starts out being invisible and is only shown if requested via DBus or input-method
 
         // Set the visible grid to the currently active layout/view to start with
@@ -275,14 +321,50 @@ fn load_css() {
     }
 }
 
+/// Get the labels of all keys that show a preview when they are pressed
+/// Keys that only display an image and keys of layouts that disabled the preview are left out
+fn get_key_preview_labels(
+    layout_meta: &HashMap<String, keyboard::LayoutMeta>,
+) -> HashMap<(String, String, String), String> {
+    let mut key_preview_labels = HashMap::new();
+    for (layout_name, layout_meta) in layout_meta {
+        if !layout_meta.key_preview {
+            continue;
+        }
+        for (view_name, key_arrangement) in &layout_meta.views {
+            for key_id in key_arrangement.get_key_arrangement().keys() {
+                let label = match layout_meta
+                    .keys
+                    .get(key_id)
+                    .map(|key_meta| &key_meta.key_display)
+                {
+                    Some(KeyDisplay::Text(text)) | Some(KeyDisplay::TextAndImage { text, .. }) => {
+                        text.to_string()
+                    }
+                    _ => continue,
+                };
+                key_preview_labels.insert(
+                    (
+                        layout_name.to_string(),
+                        view_name.to_string(),
+                        key_id.to_string(),
+                    ),
+                    label,
+                );
+            }
+        }
+    }
+    key_preview_labels
+}
+
 #[cfg(feature = "suggestions")]
 /// Create the suggestion buttons and a button to open the preferences
 fn make_suggestions_and_pref_buttons(
     relm: &relm::Relm<super::Win>,
     keyboard: &keyboard::Keyboard,
-) -> Vec<gtk::Button> {
+) -> (Vec<gtk::Button>, gtk::Button) {
     // Make the buttons to display suggestions
-    let mut buttons = make_suggestion_buttons(relm);
+    let buttons = make_suggestion_buttons(relm, NO_SUGGESTION_BUTTONS);
     // Get the names of all layouts
     let layout_names: Vec<&String> = keyboard
         .get_views()
@@ -291,9 +373,7 @@ fn make_suggestions_and_pref_buttons(
         .collect();
     // Make a button that openes the preferences
     let preferences_button = make_pref_button(relm, layout_names);
-    // Add the preferences button
-    buttons.push(preferences_button);
-    buttons
+    (buttons, preferences_button)
 }
 
 #[cfg(feature = "suggestions")]
@@ -304,7 +384,6 @@ fn make_pref_button(relm: &relm::Relm<super::Win>, layout_names: Vec<&String>) -
     let preferences_button = gtk::Button::new();
     preferences_button.style_context().add_class("preferences");
     preferences_button.set_label("pref");
-    preferences_button.set_hexpand(true);
     preferences_button.set_focus_on_click(false);
 
     // Add a popover to the button to select one of the available layouts
@@ -346,12 +425,15 @@ fn make_pref_button(relm: &relm::Relm<super::Win>, layout_names: Vec<&String>) -
 }
 
 #[cfg(feature = "suggestions")]
-/// Makes a suggestion button
-fn make_suggestion_buttons(relm: &relm::Relm<super::Win>) -> Vec<gtk::Button> {
+/// Makes the number of suggestion buttons
+pub fn make_suggestion_buttons(
+    relm: &relm::Relm<super::Win>,
+    no_buttons: usize,
+) -> Vec<gtk::Button> {
     // Make a vector of strings
     // These will be the labels of the suggestion buttons they start with
     let mut buttons = Vec::new();
-    for _ in 0..3 {
+    for _ in 0..no_buttons {
         // .. make a new button
         let new_suggestion_button = gtk::Button::new();
         new_suggestion_button
@@ -376,3 +458,26 @@ fn make_suggestion_buttons(relm: &relm::Relm<super::Win>) -> Vec<gtk::Button> {
 
     buttons
 }
+
+#[cfg(feature = "suggestions")]
+/// Make a GestureLongPress for a suggestion button
+/// A long press on a suggestion removes the suggested word from the user dictionary
+pub fn make_forget_word_gesture(
+    relm: &relm::Relm<super::Win>,
+    button: &gtk::Button,
+) -> gtk::GestureLongPress {
+    let long_press_gesture = gtk::GestureLongPress::new(button);
+    // The gesture handles the events before the button does. Claiming them prevents the button from also submitting the word
+    long_press_gesture.set_propagation_phase(gtk::PropagationPhase::Capture);
+    let relm_clone = relm.clone();
+    let button_clone = button.clone();
+    long_press_gesture.connect_pressed(move |long_press_gesture, _, _| {
+        long_press_gesture.set_state(gtk::EventSequenceState::Claimed);
+        if let Some(word) = button_clone.label() {
+            if !word.is_empty() {
+                relm_clone.stream().emit(Msg::ForgetWord(word.to_string()));
+            }
+        }
+    });
+    long_press_gesture
+}
@@ -1,6 +1,8 @@
 // Imports from other crates
 //use gtk::prelude::*;
-use gtk::prelude::{ButtonExt, ContainerExt, GridExt, StackExt, StyleContextExt, WidgetExt};
+use gtk::prelude::{
+    ButtonExt, Cast, ContainerExt, GridExt, OverlayExt, StackExt, StyleContextExt, WidgetExt,
+};
 use gtk::{Grid, Popover, Stack, ToggleButton};
 
 use std::collections::HashMap;
@@ -103,29 +105,58 @@ impl GridBuilder {
                 button.style_context().add_class(style_classes);
             }
         }
-        // Set the button to display a label or an icon
-        match &key_meta.key_display {
-            KeyDisplay::Text(label_text) => button.set_label(label_text),
-            KeyDisplay::Image(icon_name) => {
-                if let Some(icon_dir_abs) =
-                    directories::get_absolute_path(directories::ICON_DIR_REL)
-                {
-                    let mut icon_path = icon_dir_abs;
-                    icon_path.push(icon_name);
-                    let image = gtk::Image::from_file(&icon_path);
-                    button.set_image(Some(&image));
-                    button.set_always_show_image(true);
-                } else {
-                    error!(
-                        "Unable to locate the image to display for button '{}'",
-                        key_id
-                    );
-                }
+        // Set the button to display a label, an icon or both
+        let (label_text, icon_name) = match &key_meta.key_display {
+            KeyDisplay::Text(label_text) => (Some(label_text), None),
+            KeyDisplay::Image(icon_name) => (None, Some(icon_name)),
+            KeyDisplay::TextAndImage { text, image } => (Some(text), Some(image)),
+        };
+        let image = icon_name.and_then(|icon_name| GridBuilder::make_image(key_id, icon_name));
+        if let Some(hint) = &key_meta.hint {
+            // The hint is shown in the corner of the button on top of the label and the icon
+            let content = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+            content.set_halign(gtk::Align::Center);
+            if let Some(image) = &image {
+                content.add(image);
+            }
+            if let Some(label_text) = label_text {
+                content.add(&gtk::Label::new(Some(label_text)));
+            }
+            let hint_label = gtk::Label::new(Some(hint));
+            hint_label.style_context().add_class("hint");
+            hint_label.set_halign(gtk::Align::End);
+            hint_label.set_valign(gtk::Align::Start);
+            let overlay = gtk::Overlay::new();
+            overlay.add(&content);
+            overlay.add_overlay(&hint_label);
+            button.add(&overlay);
+        } else {
+            if let Some(label_text) = label_text {
+                button.set_label(label_text);
+            }
+            if let Some(image) = &image {
+                button.set_image(Some(image));
+                button.set_always_show_image(true);
             }
         }
         button
     }
 
+    /// Make the image of the icon to display on a button
+    fn make_image(key_id: &str, icon_name: &str) -> Option<gtk::Image> {
+        if let Some(icon_dir_abs) = directories::get_absolute_path(directories::ICON_DIR_REL) {
+            let mut icon_path = icon_dir_abs;
+            icon_path.push(icon_name);
+            Some(gtk::Image::from_file(&icon_path))
+        } else {
+            error!(
+                "Unable to locate the image to display for button '{}'",
+                key_id
+            );
+            None
+        }
+    }
+
     /// Attaches a popover to the toggle button, if there is one defined in the key meta info. If no popover is supposed to get added, it returns 'None
     fn attach_popover(
         relm: &relm::Relm<crate::user_interface::Win>,
@@ -176,6 +207,20 @@ impl GridBuilder {
         popover_option
     }
 
+    /// Returns the buttons of a popover that was made by 'attach_popover'
+    pub fn get_popover_buttons(popover: &Popover) -> Vec<gtk::Button> {
+        // The popover contains a vertical box with a horizontal box for each row of buttons
+        popover
+            .children()
+            .into_iter()
+            .filter_map(|v_box| v_box.downcast::<gtk::Box>().ok())
+            .flat_map(|v_box| v_box.children())
+            .filter_map(|h_box| h_box.downcast::<gtk::Box>().ok())
+            .flat_map(|h_box| h_box.children())
+            .filter_map(|button| button.downcast::<gtk::Button>().ok())
+            .collect()
+    }
+
     /// Make the name of the grid.
     /// The returned name is the layout and the view name but separated by an underscore
     /// (necessary because the stack only allows setting the visible child by a string and not a tuple of
@@ -11,6 +11,9 @@ use super::relm_widget::GridBuilder;
 use super::{Msg, Orientation};
 use crate::dbus::DBusService;
 
+/// Name of the layout that is shown while a terminal is focused
+const TERMINAL_LAYOUT_NAME: &str = "terminal";
+
 /// The UIManager changes the layout/view, hides/shows the keyboard and can tell the DBusService to send a button-pressed/button-releases event to give haptic feedback.
 /// It handles all changes to the UI except for gesture paths getting displayed
 pub struct UIManager {
@@ -20,6 +23,7 @@ pub struct UIManager {
     dbus_service: DBusService,
     pub current_layout_view: (String, String),
     prev_layout: String,
+    layout_before_terminal: Option<String>, // Only 'Some' while the terminal layout is shown because a terminal is focused
 }
 
 impl UIManager {
@@ -39,6 +43,7 @@ impl UIManager {
             dbus_service,
             current_layout_view,
             prev_layout,
+            layout_before_terminal: None,
         }
     }
 
@@ -66,12 +71,34 @@ impl UIManager {
     }
 
     /// Handles a change of the content hint and content purpose
-    /// CURRENTLY NOT IMPLEMENTED AND DOES NOTHING
-    pub fn change_hint_purpose(&self, content_hint: ContentHint, content_purpose: ContentPurpose) {
+    /// When a terminal is focused, the terminal layout is shown. When the terminal is no longer focused, the layout that was shown before is restored
+    pub fn change_hint_purpose(
+        &mut self,
+        content_hint: ContentHint,
+        content_purpose: ContentPurpose,
+    ) {
         info!(
-            "UI_manager tries to change the content hint/purpose to ContentHint: {:?}, ContentPurpose: {:?}. This is not implemented yet.",
+            "UI_manager changes the content hint/purpose to ContentHint: {:?}, ContentPurpose: {:?}",
             content_hint, content_purpose
-        )
+        );
+        if content_purpose == ContentPurpose::Terminal {
+            if self.layout_before_terminal.is_none() {
+                let current_layout = self.current_layout_view.0.clone();
+                if self
+                    .change_layout_view(&Some(TERMINAL_LAYOUT_NAME.to_string()), None)
+                    .is_ok()
+                {
+                    info!("Switched to the terminal layout because a terminal is focused");
+                    self.layout_before_terminal = Some(current_layout);
+                }
+            }
+        } else if let Some(layout_before_terminal) = self.layout_before_terminal.take() {
+            // The user might have switched to a different layout in the meantime
+            if self.current_layout_view.0.trim_end_matches("_wide") == TERMINAL_LAYOUT_NAME {
+                let _ = self.change_layout_view(&Some(layout_before_terminal), None);
+                // Result not relevant
+            }
+        }
     }
 
     /// Handles a change of the orientation